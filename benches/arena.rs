@@ -0,0 +1,56 @@
+//! Times `ArenaTree` against the `Rc<RefCell>` layout of `BinaryTree` on 1M
+//! random inserts and lookups. Run with `cargo bench --bench arena`.
+
+use std::time::Instant;
+
+// The crate is a binary, so the tree sources are compiled in directly. Cargo
+// builds benches with `--cfg test` but without the test harness, which strips
+// the `#[test]` functions and leaves the test modules' imports unused.
+#[path = "../src/tree/mod.rs"]
+#[allow(dead_code, unused_imports)]
+mod tree;
+
+use tree::{arena::ArenaTree, binary::BinaryTree};
+
+const N: usize = 1_000_000;
+
+fn main() {
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    let keys: Vec<u64> = (0..N)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        })
+        .collect();
+
+    let start = Instant::now();
+    let mut rc_tree: BinaryTree<u64> = BinaryTree::default();
+    for &key in &keys {
+        rc_tree.insert(key);
+    }
+    let rc_insert = start.elapsed();
+
+    let start = Instant::now();
+    let rc_found = keys.iter().filter(|&&key| rc_tree.search(key)).count();
+    let rc_lookup = start.elapsed();
+
+    let start = Instant::now();
+    let mut arena_tree: ArenaTree<u64> = ArenaTree::default();
+    for &key in &keys {
+        arena_tree.insert(key);
+    }
+    let arena_insert = start.elapsed();
+
+    let start = Instant::now();
+    let arena_found = keys.iter().filter(|&&key| arena_tree.search(key)).count();
+    let arena_lookup = start.elapsed();
+
+    assert_eq!(N, rc_found);
+    assert_eq!(N, arena_found);
+
+    println!("{:<8}{:>14}{:>14}", "layout", "insert", "lookup");
+    println!("{:<8}{:>14?}{:>14?}", "rc", rc_insert, rc_lookup);
+    println!("{:<8}{:>14?}{:>14?}", "arena", arena_insert, arena_lookup);
+}
@@ -0,0 +1,1410 @@
+use std::{
+    cmp::Ordering,
+    collections::VecDeque,
+    fmt::Debug,
+    mem,
+    ops::{BitAnd, BitOr, BitXor, Bound, RangeBounds, Sub},
+};
+
+use super::{
+    binary::{Difference, Intersection, MergeIter, SymmetricDifference, Union, UnsortedError},
+    comparator::{Comparator, Natural},
+};
+
+/// Position of a node inside the arena.
+pub type Index = u32;
+
+/// Stands in for a missing child or parent.
+pub const NIL: Index = Index::MAX;
+
+#[derive(Debug, Clone)]
+pub struct Node<V> {
+    pub value: V,
+    pub size: u32,
+
+    pub parent: Index,
+    pub left: Index,
+    pub right: Index,
+}
+
+/// `BinaryTree` laid out in a single `Vec`. Links are `u32` indices instead of
+/// `Rc`/`Weak` pointers, so a node carries no refcounts or borrow flag and
+/// neighbours tend to share cache lines. Slots of removed nodes are threaded
+/// onto a free list through their `right` link and reused by later inserts.
+pub struct ArenaTree<V, C = Natural> {
+    nodes: Vec<Node<V>>,
+    root: Index,
+    free: Index,
+    length: usize,
+    comparator: C,
+}
+
+/// Double-ended in-order iterator. It follows parent links from both ends
+/// and stops once `remaining` values have been yielded, so it keeps no stack.
+pub struct Iter<'a, V, C = Natural> {
+    tree: &'a ArenaTree<V, C>,
+    front: Index,
+    back: Index,
+    remaining: usize,
+}
+
+/// Owning in-order iterator. It walks the arena it took over exactly like
+/// `Iter` does.
+pub struct IntoIter<V, C = Natural> {
+    tree: ArenaTree<V, C>,
+    front: Index,
+    back: Index,
+    remaining: usize,
+}
+
+pub struct Preorder<'a, V, C = Natural> {
+    tree: &'a ArenaTree<V, C>,
+    stack: Vec<Index>,
+}
+
+/// Post-order iterator. Each stack entry records whether the node's children
+/// have already been pushed, so a node is yielded the second time it is seen.
+pub struct Postorder<'a, V, C = Natural> {
+    tree: &'a ArenaTree<V, C>,
+    stack: Vec<(Index, bool)>,
+}
+
+pub struct LevelOrder<'a, V, C = Natural> {
+    tree: &'a ArenaTree<V, C>,
+    queue: VecDeque<Index>,
+}
+
+/// Mutable cursor over the values in order. `NIL` is the "ghost" position
+/// between the last and the first value; moving past either end lands there,
+/// and moving again wraps around.
+pub struct CursorMut<'a, V, C = Natural> {
+    current: Index,
+    tree: &'a mut ArenaTree<V, C>,
+}
+
+impl<V> Node<V> {
+    fn new(value: V, parent: Index) -> Self {
+        Self {
+            value,
+            size: 1,
+            parent,
+            left: NIL,
+            right: NIL,
+        }
+    }
+}
+
+impl<V, C: Default> Default for ArenaTree<V, C> {
+    fn default() -> Self {
+        Self::with_comparator(C::default())
+    }
+}
+
+impl<V, C> ArenaTree<V, C> {
+    /// Empty tree ordered by `comparator` instead of the natural order.
+    pub fn with_comparator(comparator: C) -> Self {
+        Self {
+            nodes: Vec::new(),
+            root: NIL,
+            free: NIL,
+            length: 0,
+            comparator,
+        }
+    }
+
+    pub fn comparator(&self) -> &C {
+        &self.comparator
+    }
+
+    /// Removes every value and releases the arena.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.root = NIL;
+        self.free = NIL;
+        self.length = 0;
+    }
+}
+
+impl<V: PartialOrd + Copy + Debug> ArenaTree<V> {
+    fn new(value: V) -> Self {
+        let mut tree = Self::default();
+        tree.insert(value);
+        tree
+    }
+
+    /// Builds a perfectly balanced tree in linear time from strictly
+    /// increasing values, failing on the first value that is not greater
+    /// than the one before it.
+    pub fn from_sorted_iter(iter: impl IntoIterator<Item = V>) -> Result<Self, UnsortedError<V>> {
+        Self::from_sorted_iter_by(iter, Natural)
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>> ArenaTree<V, C> {
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Like `from_sorted_iter`, but the values must be strictly increasing
+    /// according to `comparator`.
+    pub fn from_sorted_iter_by(
+        iter: impl IntoIterator<Item = V>,
+        comparator: C,
+    ) -> Result<Self, UnsortedError<V>> {
+        let mut values: Vec<V> = Vec::new();
+
+        for (index, value) in iter.into_iter().enumerate() {
+            if let Some(&previous) = values.last() {
+                if comparator.compare(&previous, &value) != Ordering::Less {
+                    return Err(UnsortedError {
+                        index,
+                        previous,
+                        value,
+                    });
+                }
+            }
+            values.push(value);
+        }
+
+        Ok(Self::from_sorted_vec(values, comparator))
+    }
+
+    fn from_sorted_vec(values: Vec<V>, comparator: C) -> Self {
+        let mut tree = Self::with_comparator(comparator);
+        tree.nodes.reserve_exact(values.len());
+        tree.root = tree.build_balanced(&values, NIL);
+        tree.length = values.len();
+        tree
+    }
+
+    fn build_balanced(&mut self, values: &[V], parent: Index) -> Index {
+        if values.is_empty() {
+            return NIL;
+        }
+
+        let middle = values.len() / 2;
+        let index = self.alloc(values[middle], parent);
+        let left = self.build_balanced(&values[..middle], index);
+        let right = self.build_balanced(&values[middle + 1..], index);
+
+        let node = self.node_mut(index);
+        node.left = left;
+        node.right = right;
+        node.size = values.len() as u32;
+        index
+    }
+
+    fn node(&self, index: Index) -> &Node<V> {
+        &self.nodes[index as usize]
+    }
+
+    fn node_mut(&mut self, index: Index) -> &mut Node<V> {
+        &mut self.nodes[index as usize]
+    }
+
+    fn size(&self, index: Index) -> u32 {
+        if index == NIL {
+            0
+        } else {
+            self.node(index).size
+        }
+    }
+
+    /// Stores a new leaf, reusing the head of the free list when there is one.
+    fn alloc(&mut self, value: V, parent: Index) -> Index {
+        if self.free != NIL {
+            let index = self.free;
+            self.free = self.node(index).right;
+            *self.node_mut(index) = Node::new(value, parent);
+            return index;
+        }
+
+        assert!(self.nodes.len() < NIL as usize, "arena is full");
+        self.nodes.push(Node::new(value, parent));
+        (self.nodes.len() - 1) as Index
+    }
+
+    fn release(&mut self, index: Index) {
+        let free = self.free;
+        let node = self.node_mut(index);
+        node.parent = NIL;
+        node.left = NIL;
+        node.right = free;
+        self.free = index;
+    }
+
+    /// Recomputes the subtree size of `index` and of every ancestor above it.
+    fn update_sizes(&mut self, mut index: Index) {
+        while index != NIL {
+            let (left, right) = (self.node(index).left, self.node(index).right);
+            let size = 1 + self.size(left) + self.size(right);
+            let node = self.node_mut(index);
+            node.size = size;
+            index = node.parent;
+        }
+    }
+
+    /// Inserts `value`, returning `false` and leaving the tree untouched when
+    /// it is already present.
+    pub(crate) fn insert(&mut self, value: V) -> bool {
+        if self.root == NIL {
+            self.root = self.alloc(value, NIL);
+            self.length += 1;
+            return true;
+        }
+
+        let mut cursor = self.root;
+        loop {
+            let ordering = self.comparator.compare(&value, &self.node(cursor).value);
+            let child = match ordering {
+                Ordering::Equal => return false,
+                Ordering::Less => self.node(cursor).left,
+                Ordering::Greater => self.node(cursor).right,
+            };
+
+            if child == NIL {
+                let index = self.alloc(value, cursor);
+                if ordering == Ordering::Less {
+                    self.node_mut(cursor).left = index;
+                } else {
+                    self.node_mut(cursor).right = index;
+                }
+                break;
+            }
+            cursor = child;
+        }
+
+        self.length += 1;
+        self.update_sizes(cursor);
+        true
+    }
+
+    pub(crate) fn delete(&mut self, value: V) -> bool {
+        match self.find(value) {
+            Some(target) => {
+                self.remove_node(target);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn remove_node(&mut self, target: Index) {
+        let (left, right) = (self.node(target).left, self.node(target).right);
+
+        // a node with two children takes over the value of its in-order
+        // predecessor, which has no right child and is spliced out instead
+        let removed = if left != NIL && right != NIL {
+            let predecessor = self.rightmost(left);
+            let value = self.node(predecessor).value;
+            self.node_mut(target).value = value;
+            predecessor
+        } else {
+            target
+        };
+
+        self.detach(removed);
+        self.release(removed);
+        self.length -= 1;
+    }
+
+    /// Unlinks `index`, which has at most one child, moving that child into
+    /// its place and shrinking the sizes above it.
+    fn detach(&mut self, index: Index) {
+        let node = self.node(index);
+        let (parent, child) = if node.left != NIL {
+            (node.parent, node.left)
+        } else {
+            (node.parent, node.right)
+        };
+
+        if child != NIL {
+            self.node_mut(child).parent = parent;
+        }
+
+        if parent == NIL {
+            self.root = child;
+        } else if self.node(parent).left == index {
+            self.node_mut(parent).left = child;
+        } else {
+            self.node_mut(parent).right = child;
+        }
+
+        self.update_sizes(parent);
+    }
+
+    fn leftmost(&self, mut index: Index) -> Index {
+        while self.node(index).left != NIL {
+            index = self.node(index).left;
+        }
+        index
+    }
+
+    fn rightmost(&self, mut index: Index) -> Index {
+        while self.node(index).right != NIL {
+            index = self.node(index).right;
+        }
+        index
+    }
+
+    pub fn min(&self) -> Option<V> {
+        self.root_slot().map(|_| self.node(self.first()).value)
+    }
+
+    pub fn max(&self) -> Option<V> {
+        self.root_slot().map(|_| self.node(self.last()).value)
+    }
+
+    fn successor(&self, index: Index) -> Index {
+        if self.node(index).right != NIL {
+            return self.leftmost(self.node(index).right);
+        }
+
+        let mut child = index;
+        let mut parent = self.node(index).parent;
+        while parent != NIL && self.node(parent).right == child {
+            child = parent;
+            parent = self.node(parent).parent;
+        }
+        parent
+    }
+
+    fn predecessor(&self, index: Index) -> Index {
+        if self.node(index).left != NIL {
+            return self.rightmost(self.node(index).left);
+        }
+
+        let mut child = index;
+        let mut parent = self.node(index).parent;
+        while parent != NIL && self.node(parent).left == child {
+            child = parent;
+            parent = self.node(parent).parent;
+        }
+        parent
+    }
+
+    fn find(&self, value: V) -> Option<Index> {
+        let mut cursor = self.root;
+
+        while cursor != NIL {
+            let node = self.node(cursor);
+            cursor = match self.comparator.compare(&value, &node.value) {
+                Ordering::Equal => return Some(cursor),
+                Ordering::Less => node.left,
+                Ordering::Greater => node.right,
+            };
+        }
+
+        None
+    }
+
+    pub(crate) fn search(&self, value: V) -> bool {
+        self.find(value).is_some()
+    }
+
+    pub fn iter(&self) -> Iter<'_, V, C> {
+        Iter {
+            tree: self,
+            front: self.first(),
+            back: self.last(),
+            remaining: self.length,
+        }
+    }
+
+    /// In-order iterator over the values inside `bounds`. Both ends are
+    /// located with `select`, so no subtree outside the range is visited.
+    pub fn range(&self, bounds: impl RangeBounds<V>) -> Iter<'_, V, C> {
+        let below_start = match bounds.start_bound() {
+            Bound::Included(start) => self.count_below(start, false),
+            Bound::Excluded(start) => self.count_below(start, true),
+            Bound::Unbounded => 0,
+        };
+        let up_to_end = match bounds.end_bound() {
+            Bound::Included(end) => self.count_below(end, true),
+            Bound::Excluded(end) => self.count_below(end, false),
+            Bound::Unbounded => self.length,
+        };
+
+        if up_to_end <= below_start {
+            return Iter {
+                tree: self,
+                front: NIL,
+                back: NIL,
+                remaining: 0,
+            };
+        }
+
+        Iter {
+            tree: self,
+            front: self.select_index(below_start),
+            back: self.select_index(up_to_end - 1),
+            remaining: up_to_end - below_start,
+        }
+    }
+
+    pub fn iter_preorder(&self) -> Preorder<'_, V, C> {
+        Preorder {
+            tree: self,
+            stack: self.root_slot().into_iter().collect(),
+        }
+    }
+
+    pub fn iter_postorder(&self) -> Postorder<'_, V, C> {
+        Postorder {
+            tree: self,
+            stack: self
+                .root_slot()
+                .map(|root| (root, false))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    pub fn iter_level_order(&self) -> LevelOrder<'_, V, C> {
+        LevelOrder {
+            tree: self,
+            queue: self.root_slot().into_iter().collect(),
+        }
+    }
+
+    /// The root index, if there is a root.
+    fn root_slot(&self) -> Option<Index> {
+        (self.root != NIL).then_some(self.root)
+    }
+
+    /// Index of the smallest value, `NIL` when empty.
+    fn first(&self) -> Index {
+        self.root_slot().map_or(NIL, |root| self.leftmost(root))
+    }
+
+    /// Index of the largest value, `NIL` when empty.
+    fn last(&self) -> Index {
+        self.root_slot().map_or(NIL, |root| self.rightmost(root))
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, V, C> {
+        CursorMut {
+            current: self.first(),
+            tree: self,
+        }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, V, C> {
+        CursorMut {
+            current: self.last(),
+            tree: self,
+        }
+    }
+
+    /// Places a cursor on the smallest value that is not less than `value`,
+    /// or on the ghost position when every value is smaller.
+    pub fn lower_bound_mut(&mut self, value: V) -> CursorMut<'_, V, C> {
+        let mut cursor = self.root;
+        let mut found = NIL;
+
+        while cursor != NIL {
+            let node = self.node(cursor);
+            cursor = if self.comparator.compare(&node.value, &value) == Ordering::Less {
+                node.right
+            } else {
+                found = cursor;
+                node.left
+            };
+        }
+
+        CursorMut {
+            current: found,
+            tree: self,
+        }
+    }
+
+    /// Moves all values of `other` into `self`, leaving `other` empty. When
+    /// every value of `other` is greater than every value of `self`, `other`
+    /// is copied in as a whole and joined under `self`'s maximum node in
+    /// `O(h + m)`; overlapping trees fall back to a linear merge into a
+    /// freshly balanced tree.
+    pub fn append(&mut self, other: &mut ArenaTree<V, C>) {
+        let (Some(first), Some(last)) = (other.min(), self.max()) else {
+            if self.root == NIL {
+                mem::swap(self, other);
+            }
+            return;
+        };
+
+        if self.comparator.compare(&last, &first) != Ordering::Less {
+            let merged: Vec<V> = self.union(other).collect();
+            self.clear();
+            other.clear();
+            self.nodes.reserve_exact(merged.len());
+            self.root = self.build_balanced(&merged, NIL);
+            self.length = merged.len();
+            return;
+        }
+
+        let pivot = self.rightmost(self.root);
+        self.detach(pivot);
+
+        let left = self.root;
+        let right = self.adopt(other, other.root, pivot);
+        for child in [left, right] {
+            if child != NIL {
+                self.node_mut(child).parent = pivot;
+            }
+        }
+
+        let size = 1 + self.size(left) + self.size(right);
+        let node = self.node_mut(pivot);
+        node.left = left;
+        node.right = right;
+        node.parent = NIL;
+        node.size = size;
+
+        self.root = pivot;
+        self.length = size as usize;
+        other.clear();
+    }
+
+    /// Copies the subtree of `other` rooted at `root` into this arena below
+    /// `parent`, keeping its shape and sizes, and returns the index of the
+    /// copied root. Links into the copy from `parent` are left to the caller.
+    fn adopt(&mut self, other: &ArenaTree<V, C>, root: Index, parent: Index) -> Index {
+        if root == NIL {
+            return NIL;
+        }
+
+        let copy = self.alloc(other.node(root).value, parent);
+        let mut stack = vec![(root, copy)];
+
+        while let Some((source, target)) = stack.pop() {
+            let node = other.node(source);
+            let (left, right) = (node.left, node.right);
+            self.node_mut(target).size = node.size;
+
+            for (child, as_left) in [(left, true), (right, false)] {
+                if child == NIL {
+                    continue;
+                }
+                let index = self.alloc(other.node(child).value, target);
+                if as_left {
+                    self.node_mut(target).left = index;
+                } else {
+                    self.node_mut(target).right = index;
+                }
+                stack.push((child, index));
+            }
+        }
+
+        copy
+    }
+
+    /// Returns the `k`-th smallest value, counting from zero.
+    pub fn select(&self, k: usize) -> Option<V> {
+        match self.select_index(k) {
+            NIL => None,
+            index => Some(self.node(index).value),
+        }
+    }
+
+    fn select_index(&self, k: usize) -> Index {
+        let mut cursor = self.root;
+        let mut k = k;
+
+        while cursor != NIL {
+            let node = self.node(cursor);
+            let left_size = self.size(node.left) as usize;
+
+            if k == left_size {
+                return cursor;
+            }
+
+            cursor = if k < left_size {
+                node.left
+            } else {
+                k -= left_size + 1;
+                node.right
+            };
+        }
+
+        NIL
+    }
+
+    /// Checks the structural invariants: values are strictly ordered, parent
+    /// indices match the child links, stored sizes are accurate, and every
+    /// slot of the arena is either reachable from the root or on the free
+    /// list.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.root != NIL && self.node(self.root).parent != NIL {
+            return Err("root has a parent".to_string());
+        }
+
+        let mut stack = Vec::new();
+        if self.root != NIL {
+            stack.push((self.root, None, None));
+        }
+        let mut count = 0;
+
+        while let Some((index, lower, upper)) = stack.pop() {
+            let node = self.node(index);
+            count += 1;
+
+            if count > self.nodes.len() {
+                return Err("child links form a cycle".to_string());
+            }
+
+            let below = lower.is_some_and(|l| self.comparator.compare(&node.value, &l).is_le());
+            let above = upper.is_some_and(|u| self.comparator.compare(&node.value, &u).is_ge());
+            if below || above {
+                return Err(format!("{:?} is out of order", node.value));
+            }
+
+            let expected_size = 1 + self.size(node.left) + self.size(node.right);
+            if node.size != expected_size {
+                return Err(format!(
+                    "{:?} stores size {} but its subtree holds {}",
+                    node.value, node.size, expected_size
+                ));
+            }
+
+            let children = [
+                (node.left, lower, Some(node.value)),
+                (node.right, Some(node.value), upper),
+            ];
+            for (child, lower, upper) in children {
+                if child == NIL {
+                    continue;
+                }
+                if self.node(child).parent != index {
+                    return Err(format!(
+                        "child of {:?} does not link back to it",
+                        node.value
+                    ));
+                }
+                stack.push((child, lower, upper));
+            }
+        }
+
+        if count != self.length {
+            return Err(format!(
+                "tree holds {} nodes but length is {}",
+                count, self.length
+            ));
+        }
+
+        let mut free = 0;
+        let mut cursor = self.free;
+        while cursor != NIL {
+            free += 1;
+            if count + free > self.nodes.len() {
+                return Err("free list overlaps the tree or loops".to_string());
+            }
+            cursor = self.node(cursor).right;
+        }
+
+        if count + free != self.nodes.len() {
+            return Err(format!(
+                "{} of {} slots are neither in the tree nor free",
+                self.nodes.len() - count - free,
+                self.nodes.len()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Number of values strictly smaller than `value`.
+    pub fn rank(&self, value: &V) -> usize {
+        self.count_below(value, false)
+    }
+
+    pub fn count_range(&self, bounds: impl RangeBounds<V>) -> usize {
+        self.range(bounds).len()
+    }
+
+    /// Counts values below `value` (or equal to it when `inclusive`) by adding
+    /// up the left subtree sizes along a single root-to-leaf path.
+    fn count_below(&self, value: &V, inclusive: bool) -> usize {
+        let mut cursor = self.root;
+        let mut count = 0;
+
+        while cursor != NIL {
+            let node = self.node(cursor);
+            let counted = match self.comparator.compare(&node.value, value) {
+                Ordering::Less => true,
+                Ordering::Equal => inclusive,
+                Ordering::Greater => false,
+            };
+
+            cursor = if counted {
+                count += self.size(node.left) as usize + 1;
+                node.right
+            } else {
+                node.left
+            };
+        }
+
+        count
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V> + Clone> ArenaTree<V, C> {
+    /// Moves every value greater than or equal to `key` into a new tree. The
+    /// nodes on the search path for `key` are re-linked in place, as in
+    /// `BinaryTree::split_off`; the upper part is then copied into an arena
+    /// of its own and its slots here are freed, for `O(h + k)` overall.
+    pub fn split_off(&mut self, key: &V) -> ArenaTree<V, C> {
+        let mut cursor = self.root;
+        let (mut left_root, mut left_tail) = (NIL, NIL);
+        let (mut right_root, mut right_tail) = (NIL, NIL);
+
+        while cursor != NIL {
+            // nodes below `key` keep their left subtree and hang off the
+            // rightmost slot of the left tree, the others mirror that
+            let index = cursor;
+            let parent = if self.comparator.compare(&self.node(index).value, key) == Ordering::Less
+            {
+                cursor = mem::replace(&mut self.node_mut(index).right, NIL);
+                match left_tail {
+                    NIL => left_root = index,
+                    tail => self.node_mut(tail).right = index,
+                }
+                mem::replace(&mut left_tail, index)
+            } else {
+                cursor = mem::replace(&mut self.node_mut(index).left, NIL);
+                match right_tail {
+                    NIL => right_root = index,
+                    tail => self.node_mut(tail).left = index,
+                }
+                mem::replace(&mut right_tail, index)
+            };
+
+            self.node_mut(index).parent = parent;
+        }
+
+        self.update_sizes(left_tail);
+        self.update_sizes(right_tail);
+        self.root = left_root;
+        self.length = self.size(left_root) as usize;
+
+        let mut split = Self::with_comparator(self.comparator.clone());
+        split.nodes.reserve_exact(self.size(right_root) as usize);
+        split.root = split.adopt(self, right_root, NIL);
+        split.length = split.size(split.root) as usize;
+
+        let mut stack = vec![right_root];
+        while let Some(index) = stack.pop() {
+            if index != NIL {
+                let node = self.node(index);
+                stack.extend([node.left, node.right]);
+                self.release(index);
+            }
+        }
+
+        split
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>> ArenaTree<V, C> {
+    pub fn union<'a>(&'a self, other: &'a ArenaTree<V, C>) -> Union<'a, V, C, Iter<'a, V, C>> {
+        Union(self.merge(other))
+    }
+
+    pub fn intersection<'a>(
+        &'a self,
+        other: &'a ArenaTree<V, C>,
+    ) -> Intersection<'a, V, C, Iter<'a, V, C>> {
+        Intersection(self.merge(other))
+    }
+
+    /// Values in `self` that are not in `other`.
+    pub fn difference<'a>(
+        &'a self,
+        other: &'a ArenaTree<V, C>,
+    ) -> Difference<'a, V, C, Iter<'a, V, C>> {
+        Difference(self.merge(other))
+    }
+
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: &'a ArenaTree<V, C>,
+    ) -> SymmetricDifference<'a, V, C, Iter<'a, V, C>> {
+        SymmetricDifference(self.merge(other))
+    }
+
+    pub fn is_subset(&self, other: &ArenaTree<V, C>) -> bool {
+        self.length <= other.length && self.difference(other).next().is_none()
+    }
+
+    pub fn is_superset(&self, other: &ArenaTree<V, C>) -> bool {
+        other.is_subset(self)
+    }
+
+    fn merge<'a>(&'a self, other: &'a ArenaTree<V, C>) -> MergeIter<'a, V, C, Iter<'a, V, C>> {
+        MergeIter::new(self.iter(), other.iter(), &self.comparator)
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V> + Default> FromIterator<V> for ArenaTree<V, C> {
+    /// Sorts and deduplicates the values before building a balanced tree.
+    fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
+        let comparator = C::default();
+        let mut values: Vec<V> = iter.into_iter().collect();
+        values.sort_by(|a, b| comparator.compare(a, b));
+        values.dedup_by(|a, b| comparator.compare(a, b) == Ordering::Equal);
+
+        Self::from_sorted_vec(values, comparator)
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>> Iterator for Iter<'_, V, C> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let value = self.tree.node(self.front).value;
+        self.front = self.tree.successor(self.front);
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>> DoubleEndedIterator for Iter<'_, V, C> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let value = self.tree.node(self.back).value;
+        self.back = self.tree.predecessor(self.back);
+        self.remaining -= 1;
+        Some(value)
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>> ExactSizeIterator for Iter<'_, V, C> {}
+
+impl<V: Copy + Debug, C: Comparator<V>> Iterator for IntoIter<V, C> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let value = self.tree.node(self.front).value;
+        self.front = self.tree.successor(self.front);
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>> DoubleEndedIterator for IntoIter<V, C> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let value = self.tree.node(self.back).value;
+        self.back = self.tree.predecessor(self.back);
+        self.remaining -= 1;
+        Some(value)
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>> ExactSizeIterator for IntoIter<V, C> {}
+
+impl<V: Copy + Debug, C: Comparator<V>> Iterator for Preorder<'_, V, C> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.tree.node(self.stack.pop()?);
+
+        self.stack
+            .extend([node.right, node.left].into_iter().filter(|&c| c != NIL));
+        Some(node.value)
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>> Iterator for Postorder<'_, V, C> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (index, expanded) = self.stack.pop()?;
+            let node = self.tree.node(index);
+
+            if expanded {
+                return Some(node.value);
+            }
+
+            self.stack.push((index, true));
+            for child in [node.right, node.left] {
+                if child != NIL {
+                    self.stack.push((child, false));
+                }
+            }
+        }
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>> Iterator for LevelOrder<'_, V, C> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.tree.node(self.queue.pop_front()?);
+
+        self.queue
+            .extend([node.left, node.right].into_iter().filter(|&c| c != NIL));
+        Some(node.value)
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>> CursorMut<'_, V, C> {
+    /// Value under the cursor, `None` on the ghost position.
+    pub fn peek(&self) -> Option<V> {
+        self.value(self.current)
+    }
+
+    pub fn peek_next(&self) -> Option<V> {
+        self.value(self.next_index())
+    }
+
+    pub fn peek_prev(&self) -> Option<V> {
+        self.value(self.prev_index())
+    }
+
+    pub fn move_next(&mut self) {
+        self.current = self.next_index();
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = self.prev_index();
+    }
+
+    /// Removes the current value and moves the cursor to the next one.
+    pub fn remove_current(&mut self) -> Option<V> {
+        let current = mem::replace(&mut self.current, NIL);
+        let value = self.value(current)?;
+
+        // the successor lives in the right subtree, so its slot survives even
+        // when `remove_node` frees the predecessor's slot instead of this one
+        let next = self.tree.successor(current);
+        self.tree.remove_node(current);
+        self.current = next;
+
+        Some(value)
+    }
+
+    /// Inserts `value` right after the current position. Returns `false`
+    /// without touching the tree unless `value` sorts strictly between the
+    /// current value and the next one.
+    pub fn insert_after(&mut self, value: V) -> bool {
+        let next = self.next_index();
+        let comparator = &self.tree.comparator;
+
+        if self
+            .peek()
+            .is_some_and(|v| comparator.compare(&v, &value) != Ordering::Less)
+            || self
+                .value(next)
+                .is_some_and(|n| comparator.compare(&n, &value) != Ordering::Greater)
+        {
+            return false;
+        }
+
+        // an in-order neighbour pair always has a free slot between them:
+        // either the current node has no right child, or the next node is the
+        // leftmost of that right subtree and has no left child
+        if self.current != NIL && self.tree.node(self.current).right == NIL {
+            self.attach(self.current, value, false);
+        } else if next != NIL {
+            self.attach(next, value, true);
+        } else {
+            self.tree.insert(value);
+        }
+
+        true
+    }
+
+    /// Inserts `value` right before the current position, with the same
+    /// ordering check as `insert_after`.
+    pub fn insert_before(&mut self, value: V) -> bool {
+        let prev = self.prev_index();
+        let comparator = &self.tree.comparator;
+
+        if self
+            .peek()
+            .is_some_and(|v| comparator.compare(&v, &value) != Ordering::Greater)
+            || self
+                .value(prev)
+                .is_some_and(|p| comparator.compare(&p, &value) != Ordering::Less)
+        {
+            return false;
+        }
+
+        if self.current != NIL && self.tree.node(self.current).left == NIL {
+            self.attach(self.current, value, true);
+        } else if prev != NIL {
+            self.attach(prev, value, false);
+        } else {
+            self.tree.insert(value);
+        }
+
+        true
+    }
+
+    fn value(&self, index: Index) -> Option<V> {
+        (index != NIL).then(|| self.tree.node(index).value)
+    }
+
+    fn next_index(&self) -> Index {
+        match self.current {
+            NIL => self.tree.first(),
+            current => self.tree.successor(current),
+        }
+    }
+
+    fn prev_index(&self) -> Index {
+        match self.current {
+            NIL => self.tree.last(),
+            current => self.tree.predecessor(current),
+        }
+    }
+
+    fn attach(&mut self, parent: Index, value: V, as_left: bool) {
+        let index = self.tree.alloc(value, parent);
+
+        if as_left {
+            self.tree.node_mut(parent).left = index;
+        } else {
+            self.tree.node_mut(parent).right = index;
+        }
+
+        self.tree.length += 1;
+        self.tree.update_sizes(parent);
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V> + Clone> BitOr<&ArenaTree<V, C>> for &ArenaTree<V, C> {
+    type Output = ArenaTree<V, C>;
+
+    fn bitor(self, rhs: &ArenaTree<V, C>) -> Self::Output {
+        ArenaTree::from_sorted_vec(self.union(rhs).collect(), self.comparator.clone())
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V> + Clone> BitAnd<&ArenaTree<V, C>> for &ArenaTree<V, C> {
+    type Output = ArenaTree<V, C>;
+
+    fn bitand(self, rhs: &ArenaTree<V, C>) -> Self::Output {
+        ArenaTree::from_sorted_vec(self.intersection(rhs).collect(), self.comparator.clone())
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V> + Clone> Sub<&ArenaTree<V, C>> for &ArenaTree<V, C> {
+    type Output = ArenaTree<V, C>;
+
+    fn sub(self, rhs: &ArenaTree<V, C>) -> Self::Output {
+        ArenaTree::from_sorted_vec(self.difference(rhs).collect(), self.comparator.clone())
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V> + Clone> BitXor<&ArenaTree<V, C>> for &ArenaTree<V, C> {
+    type Output = ArenaTree<V, C>;
+
+    fn bitxor(self, rhs: &ArenaTree<V, C>) -> Self::Output {
+        ArenaTree::from_sorted_vec(
+            self.symmetric_difference(rhs).collect(),
+            self.comparator.clone(),
+        )
+    }
+}
+
+impl<'a, V: Copy + Debug, C: Comparator<V>> IntoIterator for &'a ArenaTree<V, C> {
+    type Item = V;
+    type IntoIter = Iter<'a, V, C>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>> IntoIterator for ArenaTree<V, C> {
+    type Item = V;
+    type IntoIter = IntoIter<V, C>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let iter = self.iter();
+        let (front, back, remaining) = (iter.front, iter.back, iter.remaining);
+
+        IntoIter {
+            tree: self,
+            front,
+            back,
+            remaining,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{ArenaTree, Index, NIL};
+    use crate::tree::test_util::XorShift;
+    use crate::tree::{
+        binary::BinaryTree,
+        comparator::{Natural, Reverse},
+    };
+
+    /// Returns the subtree size below `index` while asserting that sizes,
+    /// parent links and ordering are consistent.
+    fn check_node(tree: &ArenaTree<i32>, index: Index, parent: Index) -> u32 {
+        if index == NIL {
+            return 0;
+        }
+
+        let node = tree.node(index);
+        assert_eq!(parent, node.parent);
+        if node.left != NIL {
+            assert!(tree.node(node.left).value < node.value);
+        }
+        if node.right != NIL {
+            assert!(tree.node(node.right).value > node.value);
+        }
+
+        let size = 1 + check_node(tree, node.left, index) + check_node(tree, node.right, index);
+        assert_eq!(size, node.size);
+        size
+    }
+
+    #[test]
+    fn insert_delete_and_search() {
+        let mut tree = ArenaTree::new(10);
+        for value in [9, 11, 12, 18, 4, 3, 5] {
+            assert!(tree.insert(value));
+        }
+        assert!(!tree.insert(4));
+
+        assert!(tree.delete(10));
+        assert!(tree.delete(3));
+        assert!(!tree.delete(3));
+        assert!(!tree.search(10));
+        assert!(tree.search(12));
+
+        assert_eq!(6, tree.len());
+        assert_eq!(6, check_node(&tree, tree.root, NIL));
+        assert_eq!(vec![4, 5, 9, 11, 12, 18], tree.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn free_slots_are_reused() {
+        let mut tree: ArenaTree<i32> = (0..8).collect();
+
+        for value in [1, 5, 6] {
+            tree.delete(value);
+        }
+        for value in [20, 21, 22] {
+            tree.insert(value);
+        }
+
+        assert_eq!(8, tree.nodes.len());
+        assert_eq!(NIL, tree.free);
+        assert_eq!(8, check_node(&tree, tree.root, NIL));
+        assert_eq!(
+            vec![0, 2, 3, 4, 7, 20, 21, 22],
+            tree.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn ordered_queries() {
+        let tree = ArenaTree::from_sorted_iter([3, 4, 5, 9, 10, 11, 12, 18]).unwrap();
+
+        assert_eq!(Some(9), tree.select(3));
+        assert_eq!(None, tree.select(8));
+        assert_eq!(4, tree.rank(&10));
+        assert_eq!(vec![5, 9, 10], tree.range(5..11).collect::<Vec<_>>());
+        assert_eq!(
+            vec![18, 12],
+            tree.range(11..).rev().take(2).collect::<Vec<_>>()
+        );
+        assert_eq!(0, tree.range(13..18).len());
+        assert!(ArenaTree::from_sorted_iter([1, 3, 2]).is_err());
+
+        let mut reversed = ArenaTree::with_comparator(Reverse(Natural));
+        for value in [3, 1, 2] {
+            reversed.insert(value);
+        }
+        assert_eq!(vec![3, 2, 1], reversed.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn random_operations_match_btreeset() {
+        let mut rng = XorShift::new(0x9E37_79B9_7F4A_7C15);
+        let mut tree: ArenaTree<i32> = ArenaTree::default();
+        let mut expected = BTreeSet::new();
+
+        for _ in 0..5000 {
+            let value = (rng.next_u64() % 512) as i32;
+            if rng.next_u64().is_multiple_of(3) {
+                assert_eq!(expected.remove(&value), tree.delete(value));
+            } else {
+                assert_eq!(expected.insert(value), tree.insert(value));
+            }
+        }
+
+        assert_eq!(expected.len(), check_node(&tree, tree.root, NIL) as usize);
+        assert!(tree.iter().eq(expected.iter().copied()));
+        assert!(tree.iter().rev().eq(expected.iter().rev().copied()));
+    }
+
+    fn tree_of(values: &[i32]) -> ArenaTree<i32> {
+        let mut tree = ArenaTree::default();
+        for &value in values {
+            tree.insert(value);
+        }
+        tree
+    }
+
+    #[test]
+    fn traversals_match_binary_tree() {
+        let values = [8, 3, 10, 1, 6, 14, 4, 7, 13];
+        let arena = tree_of(&values);
+        let mut binary: BinaryTree<i32> = BinaryTree::default();
+        for value in values {
+            binary.insert(value);
+        }
+
+        assert!(arena.iter_preorder().eq(binary.iter_preorder()));
+        assert!(arena.iter_postorder().eq(binary.iter_postorder()));
+        assert!(arena.iter_level_order().eq(binary.iter_level_order()));
+        assert_eq!(
+            vec![8, 3, 1, 6, 4, 7, 10, 14, 13],
+            arena.iter_preorder().collect::<Vec<_>>()
+        );
+        assert_eq!((Some(1), Some(14)), (arena.min(), arena.max()));
+        assert_eq!(4, arena.count_range(4..=8));
+
+        let mut owned = arena.into_iter();
+        assert_eq!(9, owned.len());
+        assert_eq!(Some(14), owned.next_back());
+        assert_eq!(vec![1, 3, 4, 6, 7, 8, 10, 13], owned.collect::<Vec<_>>());
+
+        let empty = ArenaTree::<i32>::default();
+        assert_eq!((None, None), (empty.min(), empty.max()));
+        assert_eq!(0, empty.iter_postorder().count());
+    }
+
+    #[test]
+    fn set_algebra() {
+        let a = tree_of(&[5, 1, 9, 3, 7]);
+        let b = tree_of(&[4, 3, 8, 5, 10]);
+
+        assert_eq!(
+            vec![1, 3, 4, 5, 7, 8, 9, 10],
+            a.union(&b).collect::<Vec<_>>()
+        );
+        assert_eq!(vec![3, 5], a.intersection(&b).collect::<Vec<_>>());
+        assert_eq!(vec![1, 7, 9], a.difference(&b).collect::<Vec<_>>());
+        assert_eq!(
+            vec![1, 4, 7, 8, 9, 10],
+            a.symmetric_difference(&b).collect::<Vec<_>>()
+        );
+
+        let union = &a | &b;
+        assert!(union.validate().is_ok());
+        assert_eq!(8, union.len());
+        assert_eq!(vec![3, 5], (&a & &b).iter().collect::<Vec<_>>());
+        assert_eq!(vec![1, 7, 9], (&a - &b).iter().collect::<Vec<_>>());
+        assert_eq!(
+            vec![1, 4, 7, 8, 9, 10],
+            (&a ^ &b).iter().collect::<Vec<_>>()
+        );
+
+        assert!(tree_of(&[3, 9]).is_subset(&a));
+        assert!(a.is_superset(&tree_of(&[3, 9])));
+        assert!(!tree_of(&[3, 4]).is_subset(&a));
+    }
+
+    #[test]
+    fn split_off_and_append() {
+        let values = [10, 5, 15, 3, 7, 12, 18, 1, 4, 6, 8, 11, 13, 17, 19];
+        let mut sorted = values.to_vec();
+        sorted.sort();
+
+        for key in [0, 3, 6, 10, 11, 15, 19, 25] {
+            let mut left = tree_of(&values);
+            let mut right = left.split_off(&key);
+
+            assert!(left.validate().is_ok());
+            assert!(right.validate().is_ok());
+            assert!(left.iter().all(|v| v < key));
+            assert!(right.iter().all(|v| v >= key));
+            assert_eq!(15, left.len() + right.len());
+
+            left.append(&mut right);
+            assert!(left.validate().is_ok());
+            assert!(right.is_empty());
+            assert!(left.iter().eq(sorted.iter().copied()));
+        }
+
+        let mut tree = tree_of(&[2, 1, 3]);
+        tree.append(&mut tree_of(&[2, 9, 0]));
+        assert!(tree.validate().is_ok());
+        assert_eq!(vec![0, 1, 2, 3, 9], tree.iter().collect::<Vec<_>>());
+
+        let mut empty: ArenaTree<i32> = ArenaTree::default();
+        empty.append(&mut tree);
+        assert_eq!(5, empty.len());
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn cursor_edits_in_order() {
+        let mut tree = tree_of(&[10, 5, 15]);
+        let mut cursor = tree.lower_bound_mut(10);
+
+        assert!(!cursor.insert_after(15));
+        assert!(!cursor.insert_before(4));
+        assert!(cursor.insert_after(12));
+        assert!(cursor.insert_before(7));
+        assert_eq!(Some(12), cursor.peek_next());
+
+        assert_eq!(Some(10), cursor.remove_current());
+        assert_eq!(Some(12), cursor.peek());
+        assert_eq!(Some(7), cursor.peek_prev());
+
+        // on the ghost position `insert_after` prepends and `insert_before`
+        // appends
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(None, cursor.peek());
+        assert!(cursor.insert_after(1));
+        assert!(cursor.insert_before(20));
+        cursor.move_next();
+        assert_eq!(Some(1), cursor.peek());
+
+        assert!(tree.validate().is_ok());
+        assert_eq!(vec![1, 5, 7, 12, 15, 20], tree.iter().collect::<Vec<_>>());
+        assert_eq!(Some(20), tree.cursor_back_mut().peek());
+
+        let mut empty: ArenaTree<i32> = ArenaTree::default();
+        assert!(empty.cursor_front_mut().insert_after(3));
+        assert_eq!(vec![3], empty.iter().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,527 @@
+use std::{
+    cell::{Ref, RefCell},
+    cmp::max,
+    fmt::Debug,
+    rc::{Rc, Weak},
+};
+
+use super::binary::{BackLink, Link};
+
+#[derive(Debug, Clone)]
+pub struct Node<V> {
+    pub value: V,
+    pub height: usize,
+
+    pub parent: BackLink<Node<V>>,
+    pub left: Link<Node<V>>,
+    pub right: Link<Node<V>>,
+}
+
+/// Self-balancing variant of `BinaryTree`: every node keeps the height of its
+/// subtree and `insert`/`delete` rotate on the way back up, so the depth stays
+/// within `1.44 * log2(n)`.
+pub struct AvlTree<V> {
+    root: Link<Node<V>>,
+    length: usize,
+}
+
+impl<V> From<Node<V>> for Link<Node<V>> {
+    fn from(value: Node<V>) -> Self {
+        Some(Rc::new(RefCell::new(value)))
+    }
+}
+
+impl<V> Node<V> {
+    fn new(value: V) -> Self {
+        Self {
+            value,
+            height: 1,
+            parent: None,
+            left: None,
+            right: None,
+        }
+    }
+
+    fn with_parent(mut self, parent: &Rc<RefCell<Node<V>>>) -> Self {
+        self.parent = Some(Rc::downgrade(parent));
+        self
+    }
+}
+
+fn height<V>(node: &Link<Node<V>>) -> usize {
+    node.as_ref().map_or(0, |n| n.borrow().height)
+}
+
+fn update_height<V>(node: &Rc<RefCell<Node<V>>>) {
+    let new_height = {
+        let n = node.borrow();
+        1 + max(height(&n.left), height(&n.right))
+    };
+    node.borrow_mut().height = new_height;
+}
+
+fn balance_factor<V>(node: &Rc<RefCell<Node<V>>>) -> isize {
+    let n = node.borrow();
+    height(&n.left) as isize - height(&n.right) as isize
+}
+
+impl<V: PartialEq + PartialOrd + Copy + Debug> AvlTree<V> {
+    pub fn new(value: V) -> Self {
+        Self {
+            root: Node::new(value).into(),
+            length: 1,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Height of the whole tree, `0` when it is empty.
+    pub fn height(&self) -> usize {
+        height(&self.root)
+    }
+
+    /// Inserts `value` and rebalances the path back to the root. Returns
+    /// `false` without touching the tree when the value is already present.
+    pub fn insert(&mut self, value: V) -> bool {
+        let mut cursor = match self.root.clone() {
+            Some(root) => root,
+            None => {
+                self.root = Node::new(value).into();
+                self.length += 1;
+                return true;
+            }
+        };
+
+        loop {
+            let next = if value < cursor.borrow().value {
+                cursor.borrow().left.clone()
+            } else if value > cursor.borrow().value {
+                cursor.borrow().right.clone()
+            } else {
+                return false;
+            };
+
+            match next {
+                Some(node) => cursor = node,
+                None => break,
+            }
+        }
+
+        let node = Node::new(value).with_parent(&cursor).into();
+        if value < cursor.borrow().value {
+            cursor.borrow_mut().left = node;
+        } else {
+            cursor.borrow_mut().right = node;
+        }
+
+        self.length += 1;
+        self.rebalance(Some(cursor));
+        true
+    }
+
+    /// Removes `value`, replacing a node with two children by its in-order
+    /// predecessor, then rebalances from the parent of the spliced node.
+    pub fn delete(&mut self, value: V) -> bool {
+        let target = match self.find(value) {
+            Some(target) => target,
+            None => return false,
+        };
+
+        let has_both = {
+            let t = target.borrow();
+            t.left.is_some() && t.right.is_some()
+        };
+
+        let removed = if has_both {
+            let left = target.borrow().left.clone().unwrap();
+            let predecessor = self.max(left).unwrap();
+            let predecessor_value = predecessor.borrow().value;
+            target.borrow_mut().value = predecessor_value;
+            predecessor
+        } else {
+            target
+        };
+
+        let (parent, child) = {
+            let mut r = removed.borrow_mut();
+            let child = r.left.take().or_else(|| r.right.take());
+            (r.parent.take(), child)
+        };
+
+        if let Some(ref c) = child {
+            c.borrow_mut().parent = parent.clone();
+        }
+        self.replace_child(&parent, &removed, child);
+
+        self.length -= 1;
+        self.rebalance(parent.and_then(|p| p.upgrade()));
+        true
+    }
+
+    pub fn min(&self, node: Rc<RefCell<Node<V>>>) -> Link<Node<V>> {
+        let mut min_node = node;
+
+        loop {
+            let left = min_node.borrow().left.clone();
+            match left {
+                Some(l) => min_node = l,
+                None => break,
+            }
+        }
+
+        Some(min_node)
+    }
+
+    pub fn max(&self, node: Rc<RefCell<Node<V>>>) -> Link<Node<V>> {
+        let mut max_node = node;
+
+        loop {
+            let right = max_node.borrow().right.clone();
+            match right {
+                Some(r) => max_node = r,
+                None => break,
+            }
+        }
+
+        Some(max_node)
+    }
+
+    pub fn find(&self, value: V) -> Link<Node<V>> {
+        let mut cursor = self.root.clone();
+
+        while let Some(node) = cursor {
+            if node.borrow().value == value {
+                return Some(node);
+            }
+
+            cursor = if value < node.borrow().value {
+                node.borrow().left.clone()
+            } else {
+                node.borrow().right.clone()
+            };
+        }
+
+        None
+    }
+
+    pub fn search(&self, value: V) -> bool {
+        self.find(value).is_some()
+    }
+
+    /// Checks the AVL invariants: values are ordered, stored heights are
+    /// accurate, no balance factor leaves `-1..=1`, each child links back to
+    /// its parent and the length matches the number of nodes.
+    pub fn validate(&self) -> Result<(), String> {
+        let root = match self.root {
+            Some(ref root) => root,
+            None if self.length == 0 => return Ok(()),
+            None => return Err(format!("empty tree reports length {}", self.length)),
+        };
+
+        if root.borrow().parent.is_some() {
+            return Err("root has a parent".to_string());
+        }
+
+        let mut count = 0;
+        Self::validate_node(root, None, None, &mut count)?;
+        if count != self.length {
+            return Err(format!(
+                "tree holds {} nodes but length is {}",
+                count, self.length
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the real height of `node`.
+    fn validate_node(
+        node: &Rc<RefCell<Node<V>>>,
+        lower: Option<V>,
+        upper: Option<V>,
+        count: &mut usize,
+    ) -> Result<usize, String> {
+        let n = node.borrow();
+        *count += 1;
+
+        if lower.is_some_and(|l| n.value <= l) || upper.is_some_and(|u| n.value >= u) {
+            return Err(format!("{:?} is out of order", n.value));
+        }
+
+        let mut heights = [0, 0];
+        for (i, child) in [&n.left, &n.right].into_iter().enumerate() {
+            let Some(c) = child else {
+                continue;
+            };
+
+            let linked = c.borrow().parent.as_ref().and_then(Weak::upgrade);
+            if !linked.is_some_and(|p| Rc::ptr_eq(&p, node)) {
+                return Err(format!("child of {:?} does not link back to it", n.value));
+            }
+
+            heights[i] = if i == 0 {
+                Self::validate_node(c, lower, Some(n.value), count)?
+            } else {
+                Self::validate_node(c, Some(n.value), upper, count)?
+            };
+        }
+
+        if heights[0].abs_diff(heights[1]) > 1 {
+            return Err(format!(
+                "{:?} is out of balance: {} vs {}",
+                n.value, heights[0], heights[1]
+            ));
+        }
+
+        let real_height = 1 + max(heights[0], heights[1]);
+        if n.height != real_height {
+            return Err(format!(
+                "{:?} stores height {} but is {} high",
+                n.value, n.height, real_height
+            ));
+        }
+
+        Ok(real_height)
+    }
+
+    pub fn traverse(&self, node: &Ref<'_, Node<V>>, collector: &mut Vec<V>) {
+        if let Some(ref l) = node.left {
+            self.traverse(&l.borrow(), collector);
+        }
+
+        collector.push(node.value);
+
+        if let Some(ref r) = node.right {
+            self.traverse(&r.borrow(), collector);
+        }
+    }
+
+    /// Walks from `start` up to the root, refreshing heights and rotating any
+    /// node whose balance factor left the `-1..=1` range.
+    fn rebalance(&mut self, start: Link<Node<V>>) {
+        let mut cursor = start;
+
+        while let Some(node) = cursor {
+            update_height(&node);
+
+            let node = match balance_factor(&node) {
+                2 => {
+                    let left = node.borrow().left.clone().unwrap();
+                    if balance_factor(&left) < 0 {
+                        self.rotate_left(left);
+                    }
+                    self.rotate_right(node)
+                }
+                -2 => {
+                    let right = node.borrow().right.clone().unwrap();
+                    if balance_factor(&right) > 0 {
+                        self.rotate_right(right);
+                    }
+                    self.rotate_left(node)
+                }
+                _ => node,
+            };
+
+            cursor = node.borrow().parent.as_ref().and_then(Weak::upgrade);
+        }
+    }
+
+    fn rotate_left(&mut self, node: Rc<RefCell<Node<V>>>) -> Rc<RefCell<Node<V>>> {
+        let pivot = node.borrow_mut().right.take().unwrap();
+        let inner = pivot.borrow_mut().left.take();
+
+        if let Some(ref i) = inner {
+            i.borrow_mut().parent = Some(Rc::downgrade(&node));
+        }
+        node.borrow_mut().right = inner;
+
+        let parent = node.borrow_mut().parent.take();
+        pivot.borrow_mut().parent = parent.clone();
+        self.replace_child(&parent, &node, Some(pivot.clone()));
+
+        node.borrow_mut().parent = Some(Rc::downgrade(&pivot));
+        pivot.borrow_mut().left = Some(node.clone());
+
+        update_height(&node);
+        update_height(&pivot);
+        pivot
+    }
+
+    fn rotate_right(&mut self, node: Rc<RefCell<Node<V>>>) -> Rc<RefCell<Node<V>>> {
+        let pivot = node.borrow_mut().left.take().unwrap();
+        let inner = pivot.borrow_mut().right.take();
+
+        if let Some(ref i) = inner {
+            i.borrow_mut().parent = Some(Rc::downgrade(&node));
+        }
+        node.borrow_mut().left = inner;
+
+        let parent = node.borrow_mut().parent.take();
+        pivot.borrow_mut().parent = parent.clone();
+        self.replace_child(&parent, &node, Some(pivot.clone()));
+
+        node.borrow_mut().parent = Some(Rc::downgrade(&pivot));
+        pivot.borrow_mut().right = Some(node.clone());
+
+        update_height(&node);
+        update_height(&pivot);
+        pivot
+    }
+
+    /// Points whichever slot held `old` (a child of `parent`, or the root) at
+    /// `new` instead.
+    fn replace_child(
+        &mut self,
+        parent: &BackLink<Node<V>>,
+        old: &Rc<RefCell<Node<V>>>,
+        new: Link<Node<V>>,
+    ) {
+        match parent.as_ref().and_then(Weak::upgrade) {
+            Some(p) => {
+                let is_left = p.borrow().left.as_ref().is_some_and(|l| Rc::ptr_eq(l, old));
+                if is_left {
+                    p.borrow_mut().left = new;
+                } else {
+                    p.borrow_mut().right = new;
+                }
+            }
+            None => self.root = new,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::{AvlTree, Node};
+    use crate::tree::test_util::XorShift;
+
+    /// Returns the real height of `node` while asserting the AVL invariants:
+    /// stored heights are accurate, balance factors stay within one and every
+    /// child points back at its parent.
+    fn check_node(node: &Rc<RefCell<Node<i32>>>) -> usize {
+        let n = node.borrow();
+        let mut heights = [0, 0];
+
+        for (i, child) in [&n.left, &n.right].into_iter().enumerate() {
+            if let Some(c) = child {
+                let parent = c
+                    .borrow()
+                    .parent
+                    .as_ref()
+                    .and_then(|p| p.upgrade())
+                    .unwrap();
+                assert!(Rc::ptr_eq(&parent, node));
+                heights[i] = check_node(c);
+            }
+        }
+
+        assert!((heights[0] as isize - heights[1] as isize).abs() <= 1);
+        assert_eq!(1 + heights[0].max(heights[1]), n.height);
+        n.height
+    }
+
+    fn check_tree(tree: &AvlTree<i32>) {
+        match tree.root {
+            Some(ref root) => {
+                assert!(root.borrow().parent.is_none());
+                check_node(root);
+            }
+            None => assert_eq!(0, tree.len()),
+        }
+    }
+
+    fn collect(tree: &AvlTree<i32>) -> Vec<i32> {
+        let mut collector = Vec::new();
+        if let Some(ref root) = tree.root {
+            tree.traverse(&root.borrow(), &mut collector);
+        }
+        collector
+    }
+
+    #[test]
+    fn sorted_inserts_stay_balanced() {
+        let mut tree = AvlTree::new(0);
+
+        for i in 1..1024 {
+            tree.insert(i);
+        }
+
+        check_tree(&tree);
+        assert_eq!(1024, tree.len());
+        assert_eq!(11, tree.height());
+        assert_eq!((0..1024).collect::<Vec<_>>(), collect(&tree));
+    }
+
+    #[test]
+    fn duplicate_insert_is_rejected() {
+        let mut tree = AvlTree::new(10);
+
+        assert!(tree.insert(5));
+        assert!(!tree.insert(5));
+        assert!(!tree.insert(10));
+        assert_eq!(2, tree.len());
+    }
+
+    #[test]
+    fn delete_tree_node() {
+        let mut tree = AvlTree::new(10);
+
+        for value in [9, 11, 12, 18, 4, 3, 5] {
+            tree.insert(value);
+        }
+
+        assert!(tree.delete(10));
+        assert!(tree.delete(4));
+        assert!(!tree.delete(4));
+        check_tree(&tree);
+
+        assert_eq!(vec![3, 5, 9, 11, 12, 18], collect(&tree));
+        assert!(!tree.search(10));
+        assert!(tree.search(18));
+    }
+
+    #[test]
+    fn delete_until_empty() {
+        let mut tree = AvlTree::new(1);
+        tree.insert(2);
+
+        assert!(tree.delete(1));
+        assert!(tree.delete(2));
+        assert!(tree.is_empty());
+        assert_eq!(0, tree.height());
+
+        tree.insert(3);
+        assert_eq!(vec![3], collect(&tree));
+    }
+
+    #[test]
+    fn random_operations_keep_height_invariant() {
+        let mut rng = XorShift::new(0x9E37_79B9_7F4A_7C15);
+        let mut tree = AvlTree::new(0);
+        let mut expected = std::collections::BTreeSet::from([0]);
+
+        for _ in 0..5000 {
+            let value = (rng.next_u64() % 512) as i32;
+            if rng.next_u64().is_multiple_of(3) {
+                assert_eq!(expected.remove(&value), tree.delete(value));
+            } else {
+                assert_eq!(expected.insert(value), tree.insert(value));
+            }
+            check_tree(&tree);
+        }
+
+        assert_eq!(expected.len(), tree.len());
+        assert_eq!(expected.into_iter().collect::<Vec<_>>(), collect(&tree));
+
+        let limit = (1.45 * ((tree.len() + 2) as f64).log2()) as usize;
+        assert!(tree.height() <= limit);
+    }
+}
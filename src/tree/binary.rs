@@ -1,26 +1,134 @@
 use std::{
     cell::{Ref, RefCell, RefMut},
-    fmt::Debug,
+    cmp::Ordering,
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt::{self, Debug, Display},
+    iter::Peekable,
+    marker::PhantomData,
+    mem,
+    ops::{BitAnd, BitOr, BitXor, Bound, RangeBounds, Sub},
     rc::{Rc, Weak},
 };
 
+use super::{
+    comparator::{Comparator, Natural},
+    render::{Layout, Render},
+};
+
 pub type LeafPtr<T> = Option<Rc<RefCell<Node<T>>>>;
 pub type ParentPtr<T> = Option<Weak<RefCell<Node<T>>>>;
 
+/// `LeafPtr` and `ParentPtr` over any node type, for the other `Rc<RefCell>`
+/// trees.
+pub(crate) type Link<N> = Option<Rc<RefCell<N>>>;
+pub(crate) type BackLink<N> = Option<Weak<RefCell<N>>>;
+
 #[derive(Debug, Clone)]
 pub struct Node<V> {
     pub value: V,
+    pub size: usize,
 
     pub parent: ParentPtr<V>,
     pub left: LeafPtr<V>,
     pub right: LeafPtr<V>,
 }
 
-pub struct BinaryTree<V> {
-    root: Rc<RefCell<Node<V>>>,
+/// Unbalanced binary search tree. Every ordering decision goes through the
+/// comparator `C`, which defaults to the values' own `PartialOrd`.
+pub struct BinaryTree<V, C = Natural> {
+    root: LeafPtr<V>,
     length: usize,
+    comparator: C,
+}
+
+/// Returned by `BinaryTree::from_sorted_iter` when the input is not strictly
+/// increasing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsortedError<V> {
+    pub index: usize,
+    pub previous: V,
+    pub value: V,
+}
+
+/// In-order walk from both ends at once. Each end keeps its own stack of
+/// pending ancestors, and the walk is over once one end reaches the node the
+/// other end yielded last.
+struct InOrder<V> {
+    front: Vec<Rc<RefCell<Node<V>>>>,
+    back: Vec<Rc<RefCell<Node<V>>>>,
+    last_front: LeafPtr<V>,
+    last_back: LeafPtr<V>,
+}
+
+/// Double-ended in-order iterator over the values inside a range. Neither end
+/// descends into a subtree that lies wholly outside the bounds.
+pub struct Range<'a, V, C = Natural> {
+    walk: InOrder<V>,
+    start: Bound<V>,
+    end: Bound<V>,
+    comparator: &'a C,
+}
+
+/// In-order iterator over every value, returned by `BinaryTree::iter`.
+pub struct Iter<'a, V> {
+    walk: InOrder<V>,
+    remaining: usize,
+    _tree: PhantomData<&'a ()>,
+}
+
+/// Owning in-order iterator. The pending nodes are kept alive by the stacks
+/// of the walk, so it does not need to borrow the tree.
+pub struct IntoIter<V> {
+    iter: Iter<'static, V>,
+}
+
+pub struct Preorder<'a, V> {
+    stack: Vec<Rc<RefCell<Node<V>>>>,
+    _tree: PhantomData<&'a ()>,
 }
 
+/// Post-order iterator. Each stack entry records whether the node's children
+/// have already been pushed, so a node is yielded the second time it is seen.
+pub struct Postorder<'a, V> {
+    stack: Vec<(Rc<RefCell<Node<V>>>, bool)>,
+    _tree: PhantomData<&'a ()>,
+}
+
+pub struct LevelOrder<'a, V> {
+    queue: VecDeque<Rc<RefCell<Node<V>>>>,
+    _tree: PhantomData<&'a ()>,
+}
+
+/// Mutable cursor over the values in order. Besides the nodes it can sit on a
+/// "ghost" position between the last and the first value; moving past either
+/// end lands there, and moving again wraps around.
+pub struct CursorMut<'a, V, C = Natural> {
+    current: LeafPtr<V>,
+    tree: &'a mut BinaryTree<V, C>,
+}
+
+/// Walks two sorted iterators in lock-step, the shared engine behind the set
+/// algebra iterators. `ArenaTree` drives it with its own `Iter`.
+pub(crate) struct MergeIter<'a, V: Copy, C, I: Iterator<Item = V>> {
+    a: Peekable<I>,
+    b: Peekable<I>,
+    comparator: &'a C,
+}
+
+pub struct Union<'a, V: Copy, C = Natural, I: Iterator<Item = V> = Iter<'a, V>>(
+    pub(crate) MergeIter<'a, V, C, I>,
+);
+pub struct Intersection<'a, V: Copy, C = Natural, I: Iterator<Item = V> = Iter<'a, V>>(
+    pub(crate) MergeIter<'a, V, C, I>,
+);
+pub struct Difference<'a, V: Copy, C = Natural, I: Iterator<Item = V> = Iter<'a, V>>(
+    pub(crate) MergeIter<'a, V, C, I>,
+);
+pub struct SymmetricDifference<'a, V: Copy, C = Natural, I: Iterator<Item = V> = Iter<'a, V>>(
+    pub(crate) MergeIter<'a, V, C, I>,
+);
+
 impl<V> From<Node<V>> for LeafPtr<V> {
     fn from(value: Node<V>) -> Self {
         Some(Rc::new(RefCell::new(value)))
@@ -33,10 +141,111 @@ impl<V> From<Node<V>> for Rc<RefCell<Node<V>>> {
     }
 }
 
-impl<V: PartialEq> Node<V> {
+fn size<V>(link: &LeafPtr<V>) -> usize {
+    link.as_ref().map_or(0, |n| n.borrow().size)
+}
+
+/// Recomputes the subtree size of `link` and of every ancestor above it.
+fn update_sizes<V>(mut link: LeafPtr<V>) {
+    while let Some(node) = link {
+        let new_size = {
+            let n = node.borrow();
+            1 + size(&n.left) + size(&n.right)
+        };
+        node.borrow_mut().size = new_size;
+        link = node.borrow().parent.as_ref().and_then(Weak::upgrade);
+    }
+}
+
+fn parent_of<V>(node: &Rc<RefCell<Node<V>>>) -> LeafPtr<V> {
+    node.borrow().parent.as_ref().and_then(Weak::upgrade)
+}
+
+fn is_left_child<V>(parent: &Rc<RefCell<Node<V>>>, node: &Rc<RefCell<Node<V>>>) -> bool {
+    parent
+        .borrow()
+        .left
+        .as_ref()
+        .is_some_and(|l| Rc::ptr_eq(l, node))
+}
+
+fn leftmost<V>(mut node: Rc<RefCell<Node<V>>>) -> Rc<RefCell<Node<V>>> {
+    loop {
+        let left = node.borrow().left.clone();
+        match left {
+            Some(l) => node = l,
+            None => return node,
+        }
+    }
+}
+
+fn rightmost<V>(mut node: Rc<RefCell<Node<V>>>) -> Rc<RefCell<Node<V>>> {
+    loop {
+        let right = node.borrow().right.clone();
+        match right {
+            Some(r) => node = r,
+            None => return node,
+        }
+    }
+}
+
+/// In-order successor, found through the right subtree or else by climbing
+/// parent links until we arrive from a left child.
+fn successor<V>(node: &Rc<RefCell<Node<V>>>) -> LeafPtr<V> {
+    let right = node.borrow().right.clone();
+    if let Some(r) = right {
+        return Some(leftmost(r));
+    }
+
+    let mut child = node.clone();
+    while let Some(parent) = parent_of(&child) {
+        if is_left_child(&parent, &child) {
+            return Some(parent);
+        }
+        child = parent;
+    }
+
+    None
+}
+
+fn predecessor<V>(node: &Rc<RefCell<Node<V>>>) -> LeafPtr<V> {
+    let left = node.borrow().left.clone();
+    if let Some(l) = left {
+        return Some(rightmost(l));
+    }
+
+    let mut child = node.clone();
+    while let Some(parent) = parent_of(&child) {
+        if !is_left_child(&parent, &child) {
+            return Some(parent);
+        }
+        child = parent;
+    }
+
+    None
+}
+
+fn below_start<V, C: Comparator<V>>(start: &Bound<V>, value: &V, comparator: &C) -> bool {
+    match start {
+        Bound::Included(start) => comparator.compare(value, start) == Ordering::Less,
+        Bound::Excluded(start) => comparator.compare(value, start) != Ordering::Greater,
+        Bound::Unbounded => false,
+    }
+}
+
+fn above_end<V, C: Comparator<V>>(end: &Bound<V>, value: &V, comparator: &C) -> bool {
+    match end {
+        Bound::Included(end) => comparator.compare(value, end) == Ordering::Greater,
+        Bound::Excluded(end) => comparator.compare(value, end) != Ordering::Less,
+        Bound::Unbounded => false,
+    }
+}
+
+impl<V> Node<V> {
     fn new(value: V) -> Self {
         Self {
             value,
+            size: 1,
             parent: None,
             left: None,
             right: None,
@@ -44,141 +253,325 @@ impl<V: PartialEq> Node<V> {
     }
 
     fn with_parent(mut self, parent: &Rc<RefCell<Node<V>>>) -> Self {
-        self.parent = Some(Rc::downgrade(&parent));
+        self.parent = Some(Rc::downgrade(parent));
         self
     }
+}
 
+impl<V: PartialEq> Node<V> {
     fn equals(&self, other: V) -> bool {
-        return self.value == other;
+        self.value == other
+    }
+}
+
+impl<V, C: Default> Default for BinaryTree<V, C> {
+    fn default() -> Self {
+        Self {
+            root: None,
+            length: 0,
+            comparator: C::default(),
+        }
+    }
+}
+
+impl<V, C> BinaryTree<V, C> {
+    /// Empty tree ordered by `comparator` instead of the natural order.
+    pub fn with_comparator(comparator: C) -> Self {
+        Self {
+            root: None,
+            length: 0,
+            comparator,
+        }
+    }
+
+    pub fn comparator(&self) -> &C {
+        &self.comparator
+    }
+
+    /// Removes every value. The nodes are unlinked one at a time; letting the
+    /// `Rc` chain drop itself would recurse once per level and overflow on a
+    /// degenerate tree.
+    pub fn clear(&mut self) {
+        let mut stack: Vec<_> = self.root.take().into_iter().collect();
+        self.length = 0;
+
+        while let Some(node) = stack.pop() {
+            let mut n = node.borrow_mut();
+            stack.extend(n.left.take());
+            stack.extend(n.right.take());
+        }
     }
 }
 
-impl<V: PartialEq + PartialOrd + Copy + Debug> BinaryTree<V> {
+impl<V: PartialOrd + Copy + Debug> BinaryTree<V> {
     fn new(value: V) -> Self {
         Self {
-            root: Rc::new(RefCell::new(Node::new(value))),
+            root: Node::new(value).into(),
             length: 1,
+            comparator: Natural,
         }
     }
 
-    fn insert(&mut self, value: V) {
-        let mut cursor = self.root.clone();
+    /// Builds a perfectly balanced tree in linear time from strictly
+    /// increasing values, failing on the first value that is not greater
+    /// than the one before it.
+    pub fn from_sorted_iter(iter: impl IntoIterator<Item = V>) -> Result<Self, UnsortedError<V>> {
+        Self::from_sorted_iter_by(iter, Natural)
+    }
+}
 
-        loop {
-            if value < cursor.borrow().value {
-                if cursor.borrow().left.is_none() {
-                    let node = Node::new(value).with_parent(&cursor);
-                    cursor.borrow_mut().left.replace(node.into());
-                    break;
+impl<V: Copy + Debug, C: Comparator<V>> BinaryTree<V, C> {
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Like `from_sorted_iter`, but the values must be strictly increasing
+    /// according to `comparator`.
+    pub fn from_sorted_iter_by(
+        iter: impl IntoIterator<Item = V>,
+        comparator: C,
+    ) -> Result<Self, UnsortedError<V>> {
+        let mut values: Vec<V> = Vec::new();
+
+        for (index, value) in iter.into_iter().enumerate() {
+            if let Some(&previous) = values.last() {
+                if comparator.compare(&previous, &value) != Ordering::Less {
+                    return Err(UnsortedError {
+                        index,
+                        previous,
+                        value,
+                    });
                 }
+            }
+            values.push(value);
+        }
+
+        Ok(Self::from_sorted_vec(values, comparator))
+    }
 
-                let new_cursor = cursor.borrow_mut().left.clone().unwrap();
-                cursor = new_cursor;
+    /// Builds a perfectly balanced tree from values that are already sorted
+    /// and free of duplicates.
+    fn from_sorted_vec(values: Vec<V>, comparator: C) -> Self {
+        Self {
+            root: Self::build_balanced(&values, None),
+            length: values.len(),
+            comparator,
+        }
+    }
+
+    fn build_balanced(values: &[V], parent: Option<&Rc<RefCell<Node<V>>>>) -> LeafPtr<V> {
+        if values.is_empty() {
+            return None;
+        }
+
+        let middle = values.len() / 2;
+        let mut node = Node::new(values[middle]);
+        node.size = values.len();
+        node.parent = parent.map(Rc::downgrade);
+
+        let node: Rc<RefCell<Node<V>>> = node.into();
+        let left = Self::build_balanced(&values[..middle], Some(&node));
+        let right = Self::build_balanced(&values[middle + 1..], Some(&node));
+        node.borrow_mut().left = left;
+        node.borrow_mut().right = right;
+
+        Some(node)
+    }
+
+    /// Inserts `value`, returning `false` and leaving the tree untouched when
+    /// it is already present.
+    pub(crate) fn insert(&mut self, value: V) -> bool {
+        let mut cursor = match self.root.clone() {
+            Some(root) => root,
+            None => {
+                self.root = Node::new(value).into();
+                self.length += 1;
+                return true;
             }
+        };
 
-            if value > cursor.borrow().value {
-                if cursor.borrow().right.is_none() {
-                    let node = Node::new(value).with_parent(&cursor);
-                    cursor.borrow_mut().right.replace(node.into());
+        loop {
+            let ordering = self.comparator.compare(&value, &cursor.borrow().value);
+            let child = match ordering {
+                Ordering::Equal => return false,
+                Ordering::Less => cursor.borrow().left.clone(),
+                Ordering::Greater => cursor.borrow().right.clone(),
+            };
+
+            match child {
+                Some(child) => cursor = child,
+                None => {
+                    let node = Node::new(value).with_parent(&cursor).into();
+                    if ordering == Ordering::Less {
+                        cursor.borrow_mut().left = node;
+                    } else {
+                        cursor.borrow_mut().right = node;
+                    }
                     break;
                 }
-
-                let new_cursor = cursor.borrow_mut().right.clone().unwrap();
-                cursor = new_cursor;
             }
         }
 
         self.length += 1;
+        update_sizes(Some(cursor));
+        true
     }
 
-    fn delete(&mut self, value: V) -> bool {
-        let detach_node = |target: Rc<RefCell<Node<V>>>| {
-            let target_value = target.borrow().value;
-            if let Some(parent) = target.borrow().parent.clone() {
-                let p = parent.upgrade().unwrap();
-                if p.borrow()
-                    .left
-                    .clone()
-                    .is_some_and(|l| l.borrow().value == target_value)
-                {
-                    p.borrow_mut().left = None;
-                } else {
-                    p.borrow_mut().right = None;
-                }
+    pub(crate) fn delete(&mut self, value: V) -> bool {
+        match self.find(value) {
+            Some(target) => {
+                self.remove_node(target);
+                true
             }
+            None => false,
+        }
+    }
+
+    fn remove_node(&mut self, target: Rc<RefCell<Node<V>>>) {
+        let has_both = {
+            let t = target.borrow();
+            t.left.is_some() && t.right.is_some()
         };
-        if let Some(target) = self.find(value) {
-            // if the node have none childs
-            if target.borrow().left.is_none() & target.borrow().right.is_none() {
-                detach_node(target.clone());
-                return true;
-            }
 
-            let left_clone = target.borrow().left.clone();
-            if let Some(left_node) = left_clone {
-                if let Some(max_node) = self.max(left_node.clone()) {
-                    let mut new_node = Node::new(max_node.borrow().value);
-                    new_node.left = target.borrow().left.clone();
-                    new_node.right = target.borrow().right.clone();
-                    if max_node.borrow().equals(left_node.borrow().value) {
-                        new_node = Node::new(left_node.borrow().value);
-                        new_node.left = left_node.borrow().left.clone();
-                        new_node.right = left_node.borrow().right.clone();
+        // a node with two children takes over the value of its in-order
+        // predecessor, which has no right child and is spliced out instead
+        let removed = if has_both {
+            let left = target.borrow().left.clone().unwrap();
+            let predecessor = self.max(left).unwrap();
+            target.borrow_mut().value = predecessor.borrow().value;
+            predecessor
+        } else {
+            target
+        };
 
+        self.detach(&removed);
+        self.length -= 1;
+    }
 
-                        target.replace(new_node);
-                        return true;
-                    }
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, V, C> {
+        CursorMut {
+            current: self.root.clone().map(leftmost),
+            tree: self,
+        }
+    }
 
-                    target.replace(new_node);
-                    detach_node(max_node.clone());
-                }
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, V, C> {
+        CursorMut {
+            current: self.root.clone().map(rightmost),
+            tree: self,
+        }
+    }
 
-                return true;
+    /// Places a cursor on the smallest value that is not less than `value`,
+    /// or on the ghost position when every value is smaller.
+    pub fn lower_bound_mut(&mut self, value: V) -> CursorMut<'_, V, C> {
+        let mut cursor = self.root.clone();
+        let mut found = None;
+
+        while let Some(node) = cursor {
+            let ordering = self.comparator.compare(&node.borrow().value, &value);
+            cursor = if ordering == Ordering::Less {
+                node.borrow().right.clone()
+            } else {
+                let left = node.borrow().left.clone();
+                found = Some(node);
+                left
+            };
+        }
+
+        CursorMut {
+            current: found,
+            tree: self,
+        }
+    }
+
+    /// Moves all values of `other` into `self`, leaving `other` empty. When
+    /// every value of `other` is greater than every value of `self` the trees
+    /// are joined under `self`'s maximum node in `O(h)`; overlapping trees
+    /// fall back to a linear merge into a freshly balanced tree.
+    pub fn append(&mut self, other: &mut BinaryTree<V, C>) {
+        let (Some(first), Some(last)) = (other.iter().next(), self.iter().next_back()) else {
+            if self.root.is_none() {
+                mem::swap(self, other);
             }
+            return;
+        };
 
-            let right_clone = target.borrow().right.clone();
-            if let Some(right_node) = right_clone {
-                if let Some(min_node) = self.min(right_node.clone()) {
-                    let mut new_node = Node::new(min_node.borrow().value);
-                    new_node.left = target.borrow().left.clone();
-                    new_node.right = target.borrow().right.clone();
+        if self.comparator.compare(&last, &first) != Ordering::Less {
+            let merged: Vec<V> = self.union(other).collect();
+            self.clear();
+            other.clear();
+            self.root = Self::build_balanced(&merged, None);
+            self.length = merged.len();
+            return;
+        }
 
-                    if min_node.borrow().equals(right_node.borrow().value) {
-                        new_node = Node::new(right_node.borrow().value);
-                        new_node.left = right_node.borrow().left.clone();
-                        new_node.right = right_node.borrow().right.clone();
+        let pivot = self.max(self.root.clone().unwrap()).unwrap();
+        self.detach(&pivot);
 
-                        target.replace(new_node);
-                        return true;
-                    }
-                    target.replace(new_node);
-                    detach_node(min_node.clone());
-                }
+        let left = self.root.take();
+        let right = other.root.take();
+        for child in left.iter().chain(right.iter()) {
+            child.borrow_mut().parent = Some(Rc::downgrade(&pivot));
+        }
 
-                return true;
+        {
+            let mut p = pivot.borrow_mut();
+            p.size = 1 + size(&left) + size(&right);
+            p.left = left;
+            p.right = right;
+        }
+
+        self.root = Some(pivot);
+        self.length = size(&self.root);
+        other.length = 0;
+    }
+
+    /// Unlinks `node`, which has at most one child, moving that child into
+    /// its place and shrinking the sizes above it.
+    fn detach(&mut self, node: &Rc<RefCell<Node<V>>>) {
+        let (parent, child) = {
+            let mut n = node.borrow_mut();
+            let child = n.left.take().or_else(|| n.right.take());
+            (n.parent.take().and_then(|p| p.upgrade()), child)
+        };
+
+        if let Some(ref c) = child {
+            c.borrow_mut().parent = parent.as_ref().map(Rc::downgrade);
+        }
+
+        match parent {
+            Some(ref p) => {
+                let is_left = p
+                    .borrow()
+                    .left
+                    .as_ref()
+                    .is_some_and(|l| Rc::ptr_eq(l, node));
+                if is_left {
+                    p.borrow_mut().left = child;
+                } else {
+                    p.borrow_mut().right = child;
+                }
             }
+            None => self.root = child,
         }
 
-        return false;
+        node.borrow_mut().size = 1;
+        update_sizes(parent);
     }
 
     fn min(&self, node: Rc<RefCell<Node<V>>>) -> LeafPtr<V> {
-        let mut min_node = node.clone();
+        let mut min_node = node;
 
         loop {
-            if min_node.borrow().left.is_none() {
-                break;
-            }
-
-            if min_node
-                .borrow()
-                .left
-                .as_ref()
-                .is_some_and(|n| n.borrow().value > min_node.borrow().value)
-            {
-                let new_node = min_node.borrow().left.clone();
-                min_node = new_node.unwrap();
+            let left = min_node.borrow().left.clone();
+            match left {
+                Some(l) => min_node = l,
+                None => break,
             }
         }
 
@@ -186,21 +579,13 @@ impl<V: PartialEq + PartialOrd + Copy + Debug> BinaryTree<V> {
     }
 
     fn max(&self, node: Rc<RefCell<Node<V>>>) -> LeafPtr<V> {
-        let mut max_node = node.clone();
+        let mut max_node = node;
 
         loop {
-            if max_node.borrow().right.is_none() {
-                break;
-            }
-
-            if max_node
-                .borrow()
-                .right
-                .as_ref()
-                .is_some_and(|n| n.borrow().value > max_node.borrow().value)
-            {
-                let new_node = max_node.borrow().right.clone();
-                max_node = new_node.unwrap();
+            let right = max_node.borrow().right.clone();
+            match right {
+                Some(r) => max_node = r,
+                None => break,
             }
         }
 
@@ -209,58 +594,186 @@ impl<V: PartialEq + PartialOrd + Copy + Debug> BinaryTree<V> {
 
     fn find(&self, value: V) -> LeafPtr<V> {
         let mut cursor = self.root.clone();
-        loop {
-            if cursor.borrow().value == value {
-                return Some(cursor);
-            }
 
-            if value < cursor.borrow().value {
-                if cursor.borrow().left.is_some() {
-                    let new_cursor = cursor.borrow().left.clone().unwrap();
-                    cursor = new_cursor
-                } else {
-                    break;
-                }
-            }
+        while let Some(node) = cursor {
+            let ordering = self.comparator.compare(&value, &node.borrow().value);
+            cursor = match ordering {
+                Ordering::Equal => return Some(node),
+                Ordering::Less => node.borrow().left.clone(),
+                Ordering::Greater => node.borrow().right.clone(),
+            };
+        }
 
-            if value > cursor.borrow().value {
-                if cursor.borrow().right.is_some() {
-                    let new_cursor = cursor.borrow().right.clone().unwrap();
-                    cursor = new_cursor;
-                } else {
-                    break;
-                }
+        None
+    }
+
+    pub(crate) fn search(&self, value: V) -> bool {
+        self.find(value).is_some()
+    }
+
+    pub fn range(&self, bounds: impl RangeBounds<V>) -> Range<'_, V, C> {
+        Range::new(self.root.clone(), bounds, &self.comparator)
+    }
+
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter::new(self.root.clone(), self.length)
+    }
+
+    pub fn iter_preorder(&self) -> Preorder<'_, V> {
+        Preorder {
+            stack: self.root.clone().into_iter().collect(),
+            _tree: PhantomData,
+        }
+    }
+
+    pub fn iter_postorder(&self) -> Postorder<'_, V> {
+        Postorder {
+            stack: self
+                .root
+                .clone()
+                .map(|root| (root, false))
+                .into_iter()
+                .collect(),
+            _tree: PhantomData,
+        }
+    }
+
+    pub fn iter_level_order(&self) -> LevelOrder<'_, V> {
+        LevelOrder {
+            queue: self.root.clone().into_iter().collect(),
+            _tree: PhantomData,
+        }
+    }
+
+    /// Returns the `k`-th smallest value, counting from zero.
+    pub fn select(&self, k: usize) -> Option<V> {
+        let mut cursor = self.root.clone();
+        let mut k = k;
+
+        while let Some(node) = cursor {
+            let left_size = size(&node.borrow().left);
+
+            if k == left_size {
+                let value = node.borrow().value;
+                return Some(value);
             }
+
+            cursor = if k < left_size {
+                node.borrow().left.clone()
+            } else {
+                k -= left_size + 1;
+                node.borrow().right.clone()
+            };
         }
-        return None;
+
+        None
+    }
+
+    /// Number of values strictly smaller than `value`.
+    pub fn rank(&self, value: &V) -> usize {
+        self.count_below(value, false)
+    }
+
+    pub fn count_range(&self, bounds: impl RangeBounds<V>) -> usize {
+        let below_start = match bounds.start_bound() {
+            Bound::Included(start) => self.count_below(start, false),
+            Bound::Excluded(start) => self.count_below(start, true),
+            Bound::Unbounded => 0,
+        };
+        let up_to_end = match bounds.end_bound() {
+            Bound::Included(end) => self.count_below(end, true),
+            Bound::Excluded(end) => self.count_below(end, false),
+            Bound::Unbounded => self.length,
+        };
+
+        up_to_end.saturating_sub(below_start)
     }
 
-    fn search(&self, value: V) -> bool {
+    /// Counts values below `value` (or equal to it when `inclusive`) by adding
+    /// up the left subtree sizes along a single root-to-leaf path.
+    fn count_below(&self, value: &V, inclusive: bool) -> usize {
         let mut cursor = self.root.clone();
-        loop {
-            if cursor.borrow().value == value {
-                return true;
+        let mut count = 0;
+
+        while let Some(node) = cursor {
+            let n = node.borrow();
+            let counted = match self.comparator.compare(&n.value, value) {
+                Ordering::Less => true,
+                Ordering::Equal => inclusive,
+                Ordering::Greater => false,
+            };
+
+            cursor = if counted {
+                count += size(&n.left) + 1;
+                n.right.clone()
+            } else {
+                n.left.clone()
+            };
+        }
+
+        count
+    }
+
+    /// Checks the structural invariants: values are strictly ordered by the
+    /// comparator, each child links back to its parent, stored subtree sizes
+    /// are accurate and the length matches the number of reachable nodes.
+    pub fn validate(&self) -> Result<(), String> {
+        let root = match self.root {
+            Some(ref root) => root,
+            None if self.length == 0 => return Ok(()),
+            None => return Err(format!("empty tree reports length {}", self.length)),
+        };
+
+        if root.borrow().parent.is_some() {
+            return Err("root has a parent".to_string());
+        }
+
+        // an explicit stack, since a degenerate tree is as deep as it is long
+        let mut stack = vec![(root.clone(), None, None)];
+        let mut count = 0;
+
+        while let Some((node, lower, upper)) = stack.pop() {
+            let n = node.borrow();
+            count += 1;
+
+            let below = lower.is_some_and(|l| self.comparator.compare(&n.value, &l).is_le());
+            let above = upper.is_some_and(|u| self.comparator.compare(&n.value, &u).is_ge());
+            if below || above {
+                return Err(format!("{:?} is out of order", n.value));
             }
 
-            if value < cursor.borrow().value {
-                if cursor.borrow().left.is_some() {
-                    let new_cursor = cursor.borrow().left.clone().unwrap();
-                    cursor = new_cursor
-                } else {
-                    break;
-                }
+            let expected_size = 1 + size(&n.left) + size(&n.right);
+            if n.size != expected_size {
+                return Err(format!(
+                    "{:?} stores size {} but its subtree holds {}",
+                    n.value, n.size, expected_size
+                ));
             }
 
-            if value > cursor.borrow().value {
-                if cursor.borrow().right.is_some() {
-                    let new_cursor = cursor.borrow().right.clone().unwrap();
-                    cursor = new_cursor;
-                } else {
-                    break;
+            let children = [
+                (&n.left, lower, Some(n.value)),
+                (&n.right, Some(n.value), upper),
+            ];
+            for (child, lower, upper) in children {
+                let Some(c) = child else {
+                    continue;
+                };
+
+                if !parent_of(c).is_some_and(|p| Rc::ptr_eq(&p, &node)) {
+                    return Err(format!("child of {:?} does not link back to it", n.value));
                 }
+                stack.push((c.clone(), lower, upper));
             }
         }
-        return false;
+
+        if count != self.length {
+            return Err(format!(
+                "tree holds {} nodes but length is {}",
+                count, self.length
+            ));
+        }
+
+        Ok(())
     }
 
     fn traverse(&mut self, node: &Ref<'_, Node<V>>, collector: &mut Vec<V>) {
@@ -276,42 +789,700 @@ impl<V: PartialEq + PartialOrd + Copy + Debug> BinaryTree<V> {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::BinaryTree;
+impl<V: Copy + Debug, C: Comparator<V> + Clone> BinaryTree<V, C> {
+    /// Moves every value greater than or equal to `key` into a new tree. Only
+    /// the nodes on the search path for `key` are re-linked; every subtree
+    /// hanging off that path moves over as a whole.
+    pub fn split_off(&mut self, key: &V) -> BinaryTree<V, C> {
+        let mut cursor = self.root.take();
+        let (mut left_root, mut left_tail): (LeafPtr<V>, LeafPtr<V>) = (None, None);
+        let (mut right_root, mut right_tail): (LeafPtr<V>, LeafPtr<V>) = (None, None);
 
-    #[test]
-    fn new_tree() {
-        let mut tree = BinaryTree::new(10);
+        while let Some(node) = cursor {
+            // nodes below `key` keep their left subtree and hang off the
+            // rightmost slot of the left tree, the others mirror that
+            let ordering = self.comparator.compare(&node.borrow().value, key);
+            let parent = if ordering == Ordering::Less {
+                cursor = node.borrow_mut().right.take();
+                let parent = left_tail.replace(node.clone());
+                match parent {
+                    Some(ref p) => p.borrow_mut().right = Some(node.clone()),
+                    None => left_root = Some(node.clone()),
+                }
+                parent
+            } else {
+                cursor = node.borrow_mut().left.take();
+                let parent = right_tail.replace(node.clone());
+                match parent {
+                    Some(ref p) => p.borrow_mut().left = Some(node.clone()),
+                    None => right_root = Some(node.clone()),
+                }
+                parent
+            };
 
-        tree.insert(9);
-        tree.insert(11);
-        tree.insert(12);
-        tree.insert(18);
-        tree.insert(4);
-        tree.insert(3);
-        tree.insert(5);
+            node.borrow_mut().parent = parent.as_ref().map(Rc::downgrade);
+        }
 
-        assert_eq!(true, tree.search(10));
-        assert_eq!(true, tree.search(9));
-        assert_eq!(true, tree.search(11));
-        assert_eq!(true, tree.search(12));
-        assert_eq!(true, tree.search(18));
+        update_sizes(left_tail);
+        update_sizes(right_tail);
 
-        assert_eq!(false, tree.search(8));
-        assert_eq!(false, tree.search(20));
+        self.root = left_root;
+        self.length = size(&self.root);
+
+        BinaryTree {
+            length: size(&right_root),
+            root: right_root,
+            comparator: self.comparator.clone(),
+        }
     }
+}
 
-    #[test]
-    fn delete_tree_node() {
-        let mut tree = BinaryTree::new(10);
+impl<V: Copy + Debug, C: Comparator<V>> BinaryTree<V, C> {
+    pub fn union<'a>(&'a self, other: &'a BinaryTree<V, C>) -> Union<'a, V, C> {
+        Union(self.merge(other))
+    }
 
-        tree.insert(9);
-        tree.insert(11);
-        tree.insert(12);
-        tree.insert(18);
-        tree.insert(4);
-        tree.insert(3);
+    pub fn intersection<'a>(&'a self, other: &'a BinaryTree<V, C>) -> Intersection<'a, V, C> {
+        Intersection(self.merge(other))
+    }
+
+    /// Values in `self` that are not in `other`.
+    pub fn difference<'a>(&'a self, other: &'a BinaryTree<V, C>) -> Difference<'a, V, C> {
+        Difference(self.merge(other))
+    }
+
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: &'a BinaryTree<V, C>,
+    ) -> SymmetricDifference<'a, V, C> {
+        SymmetricDifference(self.merge(other))
+    }
+
+    pub fn is_subset(&self, other: &BinaryTree<V, C>) -> bool {
+        self.length <= other.length && self.difference(other).next().is_none()
+    }
+
+    pub fn is_superset(&self, other: &BinaryTree<V, C>) -> bool {
+        other.is_subset(self)
+    }
+
+    fn merge<'a>(&'a self, other: &'a BinaryTree<V, C>) -> MergeIter<'a, V, C, Iter<'a, V>> {
+        MergeIter::new(self.iter(), other.iter(), &self.comparator)
+    }
+}
+
+impl<V: Debug> Display for UnsortedError<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "value {:?} at index {} does not follow {:?}",
+            self.value, self.index, self.previous
+        )
+    }
+}
+
+impl<V: Debug> Error for UnsortedError<V> {}
+
+impl<V: Copy + Debug, C: Comparator<V> + Default> FromIterator<V> for BinaryTree<V, C> {
+    /// Sorts and deduplicates the values before building a balanced tree.
+    fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
+        let comparator = C::default();
+        let mut values: Vec<V> = iter.into_iter().collect();
+        values.sort_by(|a, b| comparator.compare(a, b));
+        values.dedup_by(|a, b| comparator.compare(a, b) == Ordering::Equal);
+
+        Self::from_sorted_vec(values, comparator)
+    }
+}
+
+impl<V: Debug, C> Render for BinaryTree<V, C> {
+    /// Lays the tree out with `L`/`R` edges and each node's stored `size`.
+    /// Parent pointers are resolved after the walk, so one that leads to a
+    /// node elsewhere in the tree is drawn where it actually points.
+    fn layout(&self) -> Layout {
+        let mut layout = Layout::default();
+        let mut ids = HashMap::new();
+        let mut parents = Vec::new();
+        let mut stack: Vec<_> = self.root.iter().map(|root| (root.clone(), None)).collect();
+
+        while let Some((node, under)) = stack.pop() {
+            let n = node.borrow();
+            let id = layout.add(under, format!("{:?}", n.value));
+            layout.annotate(id, "size", n.size);
+            ids.insert(Rc::as_ptr(&node), id);
+            if let Some(parent) = &n.parent {
+                parents.push((id, parent.upgrade()));
+            }
+
+            stack.extend(n.right.clone().map(|right| (right, Some((id, "R")))));
+            stack.extend(n.left.clone().map(|left| (left, Some((id, "L")))));
+        }
+
+        for (id, parent) in parents {
+            let target = parent.and_then(|p| ids.get(&Rc::as_ptr(&p)).copied());
+            layout.link_parent(id, target);
+        }
+        layout
+    }
+}
+
+impl<V, C> Drop for BinaryTree<V, C> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<V: Copy> InOrder<V> {
+    fn new() -> Self {
+        Self {
+            front: Vec::new(),
+            back: Vec::new(),
+            last_front: None,
+            last_back: None,
+        }
+    }
+
+    /// Pushes the left spine of `link`, skipping nodes for which `skip` holds
+    /// together with their left subtrees.
+    fn descend_front(&mut self, mut link: LeafPtr<V>, skip: impl Fn(&V) -> bool) {
+        while let Some(node) = link {
+            if skip(&node.borrow().value) {
+                link = node.borrow().right.clone();
+            } else {
+                link = node.borrow().left.clone();
+                self.front.push(node);
+            }
+        }
+    }
+
+    fn descend_back(&mut self, mut link: LeafPtr<V>, skip: impl Fn(&V) -> bool) {
+        while let Some(node) = link {
+            if skip(&node.borrow().value) {
+                link = node.borrow().left.clone();
+            } else {
+                link = node.borrow().right.clone();
+                self.back.push(node);
+            }
+        }
+    }
+
+    /// Yields the next value from the front, or ends that side once it meets
+    /// the back or reaches a value for which `stop` holds.
+    fn next_front(&mut self, skip: impl Fn(&V) -> bool, stop: impl Fn(&V) -> bool) -> Option<V> {
+        let node = self.front.pop()?;
+        let value = node.borrow().value;
+        let met = self
+            .last_back
+            .as_ref()
+            .is_some_and(|back| Rc::ptr_eq(back, &node));
+
+        if met || stop(&value) {
+            self.front.clear();
+            return None;
+        }
+
+        self.descend_front(node.borrow().right.clone(), skip);
+        self.last_front = Some(node);
+        Some(value)
+    }
+
+    fn next_back(&mut self, skip: impl Fn(&V) -> bool, stop: impl Fn(&V) -> bool) -> Option<V> {
+        let node = self.back.pop()?;
+        let value = node.borrow().value;
+        let met = self
+            .last_front
+            .as_ref()
+            .is_some_and(|front| Rc::ptr_eq(front, &node));
+
+        if met || stop(&value) {
+            self.back.clear();
+            return None;
+        }
+
+        self.descend_back(node.borrow().left.clone(), skip);
+        self.last_back = Some(node);
+        Some(value)
+    }
+}
+
+impl<'a, V: Copy, C: Comparator<V>> Range<'a, V, C> {
+    fn new(root: LeafPtr<V>, bounds: impl RangeBounds<V>, comparator: &'a C) -> Self {
+        let mut range = Range {
+            walk: InOrder::new(),
+            start: bounds.start_bound().cloned(),
+            end: bounds.end_bound().cloned(),
+            comparator,
+        };
+
+        let (start, end) = (&range.start, &range.end);
+        range
+            .walk
+            .descend_front(root.clone(), |v| below_start(start, v, comparator));
+        range
+            .walk
+            .descend_back(root, |v| above_end(end, v, comparator));
+        range
+    }
+}
+
+impl<V: Copy, C: Comparator<V>> Iterator for Range<'_, V, C> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, end, comparator) = (&self.start, &self.end, self.comparator);
+        self.walk.next_front(
+            |v| below_start(start, v, comparator),
+            |v| above_end(end, v, comparator),
+        )
+    }
+}
+
+impl<V: Copy, C: Comparator<V>> DoubleEndedIterator for Range<'_, V, C> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (start, end, comparator) = (&self.start, &self.end, self.comparator);
+        self.walk.next_back(
+            |v| above_end(end, v, comparator),
+            |v| below_start(start, v, comparator),
+        )
+    }
+}
+
+impl<V: Copy> Iter<'_, V> {
+    fn new(root: LeafPtr<V>, length: usize) -> Self {
+        let mut walk = InOrder::new();
+        walk.descend_front(root.clone(), |_| false);
+        walk.descend_back(root, |_| false);
+
+        Iter {
+            walk,
+            remaining: length,
+            _tree: PhantomData,
+        }
+    }
+}
+
+impl<V: Copy> Iterator for Iter<'_, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.walk.next_front(|_| false, |_| false)?;
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<V: Copy> DoubleEndedIterator for Iter<'_, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let value = self.walk.next_back(|_| false, |_| false)?;
+        self.remaining -= 1;
+        Some(value)
+    }
+}
+
+impl<V: Copy> ExactSizeIterator for Iter<'_, V> {}
+
+impl<V: Copy> Iterator for IntoIter<V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<V: Copy> DoubleEndedIterator for IntoIter<V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+impl<V: Copy> ExactSizeIterator for IntoIter<V> {}
+
+impl<V> Drop for IntoIter<V> {
+    /// The tree's own `Drop` never sees the nodes handed to the iterator, so
+    /// whatever is left unvisited is unlinked here, the same way as `clear`.
+    fn drop(&mut self) {
+        let walk = &mut self.iter.walk;
+        let mut stack: Vec<_> = walk.front.drain(..).chain(walk.back.drain(..)).collect();
+        stack.extend(walk.last_front.take());
+        stack.extend(walk.last_back.take());
+
+        while let Some(node) = stack.pop() {
+            let mut n = node.borrow_mut();
+            stack.extend(n.left.take());
+            stack.extend(n.right.take());
+        }
+    }
+}
+
+impl<V: Copy> Iterator for Preorder<'_, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        let n = node.borrow();
+
+        self.stack.extend(n.right.clone());
+        self.stack.extend(n.left.clone());
+        Some(n.value)
+    }
+}
+
+impl<V: Copy> Iterator for Postorder<'_, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (node, expanded) = self.stack.pop()?;
+            let n = node.borrow();
+
+            if expanded {
+                return Some(n.value);
+            }
+
+            self.stack.push((node.clone(), true));
+            self.stack.extend(n.right.clone().map(|r| (r, false)));
+            self.stack.extend(n.left.clone().map(|l| (l, false)));
+        }
+    }
+}
+
+impl<V: Copy> Iterator for LevelOrder<'_, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.queue.pop_front()?;
+        let n = node.borrow();
+
+        self.queue.extend(n.left.clone());
+        self.queue.extend(n.right.clone());
+        Some(n.value)
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>> CursorMut<'_, V, C> {
+    /// Value under the cursor, `None` on the ghost position.
+    pub fn peek(&self) -> Option<V> {
+        self.current.as_ref().map(|n| n.borrow().value)
+    }
+
+    pub fn peek_next(&self) -> Option<V> {
+        self.next_node().map(|n| n.borrow().value)
+    }
+
+    pub fn peek_prev(&self) -> Option<V> {
+        self.prev_node().map(|n| n.borrow().value)
+    }
+
+    pub fn move_next(&mut self) {
+        self.current = self.next_node();
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = self.prev_node();
+    }
+
+    /// Removes the current value and moves the cursor to the next one.
+    pub fn remove_current(&mut self) -> Option<V> {
+        let current = self.current.take()?;
+        let value = current.borrow().value;
+
+        // the successor lives in the right subtree, so it survives even when
+        // `remove_node` moves the predecessor's value into `current`
+        let next = successor(&current);
+        self.tree.remove_node(current);
+        self.current = next;
+
+        Some(value)
+    }
+
+    /// Inserts `value` right after the current position. Returns `false`
+    /// without touching the tree unless `value` sorts strictly between the
+    /// current value and the next one.
+    pub fn insert_after(&mut self, value: V) -> bool {
+        let next = self.next_node();
+        let comparator = &self.tree.comparator;
+
+        if self
+            .peek()
+            .is_some_and(|v| comparator.compare(&v, &value) != Ordering::Less)
+            || next
+                .as_ref()
+                .is_some_and(|n| comparator.compare(&n.borrow().value, &value) != Ordering::Greater)
+        {
+            return false;
+        }
+
+        // an in-order neighbour pair always has a free slot between them:
+        // either the current node has no right child, or the next node is the
+        // leftmost of that right subtree and has no left child
+        match (self.current.clone(), next) {
+            (Some(current), _) if current.borrow().right.is_none() => {
+                self.attach(current, value, false)
+            }
+            (_, Some(next)) => self.attach(next, value, true),
+            (_, None) => {
+                self.tree.insert(value);
+            }
+        }
+
+        true
+    }
+
+    /// Inserts `value` right before the current position, with the same
+    /// ordering check as `insert_after`.
+    pub fn insert_before(&mut self, value: V) -> bool {
+        let prev = self.prev_node();
+        let comparator = &self.tree.comparator;
+
+        if self
+            .peek()
+            .is_some_and(|v| comparator.compare(&v, &value) != Ordering::Greater)
+            || prev
+                .as_ref()
+                .is_some_and(|p| comparator.compare(&p.borrow().value, &value) != Ordering::Less)
+        {
+            return false;
+        }
+
+        match (self.current.clone(), prev) {
+            (Some(current), _) if current.borrow().left.is_none() => {
+                self.attach(current, value, true)
+            }
+            (_, Some(prev)) => self.attach(prev, value, false),
+            (_, None) => {
+                self.tree.insert(value);
+            }
+        }
+
+        true
+    }
+
+    fn next_node(&self) -> LeafPtr<V> {
+        match self.current {
+            Some(ref node) => successor(node),
+            None => self.tree.root.clone().map(leftmost),
+        }
+    }
+
+    fn prev_node(&self) -> LeafPtr<V> {
+        match self.current {
+            Some(ref node) => predecessor(node),
+            None => self.tree.root.clone().map(rightmost),
+        }
+    }
+
+    fn attach(&mut self, parent: Rc<RefCell<Node<V>>>, value: V, as_left: bool) {
+        let node = Node::new(value).with_parent(&parent).into();
+
+        if as_left {
+            parent.borrow_mut().left = node;
+        } else {
+            parent.borrow_mut().right = node;
+        }
+
+        self.tree.length += 1;
+        update_sizes(Some(parent));
+    }
+}
+
+impl<'a, V: Copy + Debug, C: Comparator<V>, I: Iterator<Item = V>> MergeIter<'a, V, C, I> {
+    pub(crate) fn new(a: I, b: I, comparator: &'a C) -> Self {
+        Self {
+            a: a.peekable(),
+            b: b.peekable(),
+            comparator,
+        }
+    }
+
+    /// Advances whichever side holds the smaller value, or both when they
+    /// hold the same one.
+    fn next_pair(&mut self) -> (Option<V>, Option<V>) {
+        let ordering = match (self.a.peek(), self.b.peek()) {
+            (Some(a), Some(b)) => self.comparator.compare(a, b),
+            _ => Ordering::Equal,
+        };
+
+        match ordering {
+            Ordering::Less => (self.a.next(), None),
+            Ordering::Greater => (None, self.b.next()),
+            Ordering::Equal => (self.a.next(), self.b.next()),
+        }
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>, I: Iterator<Item = V>> Iterator for Union<'_, V, C, I> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (a, b) = self.0.next_pair();
+        a.or(b)
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>, I: Iterator<Item = V>> Iterator
+    for Intersection<'_, V, C, I>
+{
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.0.next_pair() {
+                (Some(a), Some(_)) => return Some(a),
+                (Some(_), None) | (None, Some(_)) => continue,
+                (None, None) => return None,
+            }
+        }
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>, I: Iterator<Item = V>> Iterator
+    for Difference<'_, V, C, I>
+{
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.0.a.peek()?;
+
+            if let (Some(a), None) = self.0.next_pair() {
+                return Some(a);
+            }
+        }
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>, I: Iterator<Item = V>> Iterator
+    for SymmetricDifference<'_, V, C, I>
+{
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.0.next_pair() {
+                (Some(a), None) => return Some(a),
+                (None, Some(b)) => return Some(b),
+                (Some(_), Some(_)) => continue,
+                (None, None) => return None,
+            }
+        }
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V> + Clone> BitOr<&BinaryTree<V, C>> for &BinaryTree<V, C> {
+    type Output = BinaryTree<V, C>;
+
+    fn bitor(self, rhs: &BinaryTree<V, C>) -> Self::Output {
+        BinaryTree::from_sorted_vec(self.union(rhs).collect(), self.comparator.clone())
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V> + Clone> BitAnd<&BinaryTree<V, C>> for &BinaryTree<V, C> {
+    type Output = BinaryTree<V, C>;
+
+    fn bitand(self, rhs: &BinaryTree<V, C>) -> Self::Output {
+        BinaryTree::from_sorted_vec(self.intersection(rhs).collect(), self.comparator.clone())
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V> + Clone> Sub<&BinaryTree<V, C>> for &BinaryTree<V, C> {
+    type Output = BinaryTree<V, C>;
+
+    fn sub(self, rhs: &BinaryTree<V, C>) -> Self::Output {
+        BinaryTree::from_sorted_vec(self.difference(rhs).collect(), self.comparator.clone())
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V> + Clone> BitXor<&BinaryTree<V, C>> for &BinaryTree<V, C> {
+    type Output = BinaryTree<V, C>;
+
+    fn bitxor(self, rhs: &BinaryTree<V, C>) -> Self::Output {
+        BinaryTree::from_sorted_vec(
+            self.symmetric_difference(rhs).collect(),
+            self.comparator.clone(),
+        )
+    }
+}
+
+impl<'a, V: Copy + Debug, C: Comparator<V>> IntoIterator for &'a BinaryTree<V, C> {
+    type Item = V;
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>> IntoIterator for BinaryTree<V, C> {
+    type Item = V;
+    type IntoIter = IntoIter<V>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        // take the root first so `Drop` has nothing left to unlink
+        let iter = Iter::new(self.root.take(), self.length);
+
+        IntoIter { iter }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, ops::Bound, rc::Rc};
+
+    use super::{update_sizes, BinaryTree, LeafPtr, Node, UnsortedError};
+    use crate::tree::{
+        comparator::{ByKey, Natural, Reverse},
+        render::{Render, RenderOptions},
+    };
+
+    fn check_sizes(node: &Rc<RefCell<Node<i32>>>) -> usize {
+        let n = node.borrow();
+        let left = n.left.as_ref().map_or(0, check_sizes);
+        let right = n.right.as_ref().map_or(0, check_sizes);
+
+        assert_eq!(1 + left + right, n.size);
+        n.size
+    }
+
+    #[test]
+    fn new_tree() {
+        let mut tree = BinaryTree::new(10);
+
+        tree.insert(9);
+        tree.insert(11);
+        tree.insert(12);
+        tree.insert(18);
+        tree.insert(4);
+        tree.insert(3);
+        tree.insert(5);
+
+        assert_eq!(true, tree.search(10));
+        assert_eq!(true, tree.search(9));
+        assert_eq!(true, tree.search(11));
+        assert_eq!(true, tree.search(12));
+        assert_eq!(true, tree.search(18));
+
+        assert_eq!(false, tree.search(8));
+        assert_eq!(false, tree.search(20));
+    }
+
+    #[test]
+    fn delete_tree_node() {
+        let mut tree = BinaryTree::new(10);
+
+        tree.insert(9);
+        tree.insert(11);
+        tree.insert(12);
+        tree.insert(18);
+        tree.insert(4);
+        tree.insert(3);
         tree.insert(5);
 
         tree.delete(11);
@@ -341,8 +1512,610 @@ mod tests {
 
 
         let mut collector = Vec::new();
-        tree.traverse(&tree.root.clone().borrow(), &mut collector);
+        tree.traverse(&tree.root.clone().unwrap().borrow(), &mut collector);
 
         assert_eq!(&[3,4,5,9,10,11,12,18], collector.as_slice());
     }
+
+    #[test]
+    fn range_tree() {
+        let mut tree = BinaryTree::new(10);
+
+        for value in [9, 11, 12, 18, 4, 3, 5] {
+            tree.insert(value);
+        }
+
+        assert_eq!(vec![4, 5, 9, 10], tree.range(4..11).collect::<Vec<_>>());
+        assert_eq!(
+            vec![4, 5, 9, 10, 11],
+            tree.range(4..=11).collect::<Vec<_>>()
+        );
+        assert_eq!(vec![12, 18], tree.range(12..).collect::<Vec<_>>());
+        assert_eq!(vec![3, 4], tree.range(..5).collect::<Vec<_>>());
+        assert_eq!(8, tree.range(..).count());
+        assert_eq!(0, tree.range(6..9).count());
+        assert_eq!(0, tree.range(19..).count());
+    }
+
+    #[test]
+    fn range_tree_double_ended() {
+        let mut tree = BinaryTree::new(10);
+
+        for value in [9, 11, 12, 18, 4, 3, 5] {
+            tree.insert(value);
+        }
+
+        assert_eq!(
+            vec![12, 11, 10, 9, 5],
+            tree.range(5..=12).rev().collect::<Vec<_>>()
+        );
+
+        let mut range = tree.range(4..18);
+        assert_eq!(Some(4), range.next());
+        assert_eq!(Some(12), range.next_back());
+        assert_eq!(Some(5), range.next());
+        assert_eq!(Some(11), range.next_back());
+        assert_eq!(Some(9), range.next());
+        assert_eq!(Some(10), range.next_back());
+        assert_eq!(None, range.next());
+        assert_eq!(None, range.next_back());
+    }
+
+    #[test]
+    fn order_statistics() {
+        let mut tree = BinaryTree::new(10);
+
+        for value in [9, 11, 12, 18, 4, 3, 5] {
+            tree.insert(value);
+        }
+
+        assert_eq!(8, check_sizes(tree.root.as_ref().unwrap()));
+        assert_eq!(Some(3), tree.select(0));
+        assert_eq!(Some(10), tree.select(4));
+        assert_eq!(Some(18), tree.select(7));
+        assert_eq!(None, tree.select(8));
+
+        assert_eq!(0, tree.rank(&3));
+        assert_eq!(4, tree.rank(&10));
+        assert_eq!(3, tree.rank(&6));
+        assert_eq!(8, tree.rank(&100));
+
+        assert_eq!(4, tree.count_range(4..11));
+        assert_eq!(5, tree.count_range(4..=11));
+        assert_eq!(2, tree.count_range(..5));
+        assert_eq!(2, tree.count_range(12..));
+        assert_eq!(0, tree.count_range(6..9));
+        assert_eq!(8, tree.count_range(..));
+    }
+
+    #[test]
+    fn order_statistics_after_delete() {
+        let mut tree = BinaryTree::new(10);
+
+        for value in [5, 15, 3, 8, 12, 20, 7, 9, 6] {
+            tree.insert(value);
+        }
+
+        // 10, 5 and 15 have two children and are replaced by their predecessors
+        assert!(tree.delete(10));
+        assert!(tree.delete(5));
+        assert!(tree.delete(15));
+        assert!(tree.delete(20));
+        assert!(!tree.delete(20));
+
+        assert_eq!(6, check_sizes(tree.root.as_ref().unwrap()));
+        assert_eq!(
+            vec![3, 6, 7, 8, 9, 12],
+            (0..6).map(|k| tree.select(k).unwrap()).collect::<Vec<_>>()
+        );
+        assert_eq!(3, tree.rank(&8));
+        assert_eq!(2, tree.count_range(7..9));
+
+        for value in [3, 6, 7, 8, 9, 12] {
+            assert!(tree.delete(value));
+        }
+        assert!(tree.root.is_none());
+        assert_eq!(None, tree.select(0));
+        assert_eq!(0, tree.count_range(..));
+    }
+
+    fn sample_tree() -> BinaryTree<i32> {
+        let mut tree = BinaryTree::new(10);
+
+        for value in [9, 11, 12, 18, 4, 3, 5] {
+            tree.insert(value);
+        }
+
+        tree
+    }
+
+    /// Builds a right-leaning chain `0 -> 1 -> ... -> n - 1` without going
+    /// through `insert`, which would take quadratic time.
+    fn degenerate_tree(n: i32) -> BinaryTree<i32> {
+        let root: Rc<RefCell<Node<i32>>> = Node::new(0).into();
+        let mut cursor = root.clone();
+
+        for value in 1..n {
+            let node: Rc<RefCell<Node<i32>>> = Node::new(value).with_parent(&cursor).into();
+            cursor.borrow_mut().right = Some(node.clone());
+            cursor = node;
+        }
+        update_sizes(Some(cursor));
+
+        BinaryTree {
+            root: Some(root),
+            length: n as usize,
+            comparator: Natural,
+        }
+    }
+
+    #[test]
+    fn iterate_tree() {
+        let tree = sample_tree();
+
+        assert_eq!(
+            vec![3, 4, 5, 9, 10, 11, 12, 18],
+            tree.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![18, 12, 11, 10, 9, 5, 4, 3],
+            tree.iter().rev().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![10, 9, 4, 3, 5, 11, 12, 18],
+            tree.iter_preorder().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![3, 5, 4, 9, 18, 12, 11, 10],
+            tree.iter_postorder().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![10, 9, 11, 4, 12, 3, 5, 18],
+            tree.iter_level_order().collect::<Vec<_>>()
+        );
+
+        let mut iter = tree.iter();
+        assert_eq!(8, iter.len());
+        assert_eq!(Some(3), iter.next());
+        assert_eq!(Some(18), iter.next_back());
+        assert_eq!(6, iter.len());
+    }
+
+    #[test]
+    fn into_iter_tree() {
+        let tree = sample_tree();
+
+        let mut borrowed = Vec::new();
+        for value in &tree {
+            borrowed.push(value);
+        }
+
+        assert_eq!(borrowed, tree.into_iter().collect::<Vec<_>>());
+        assert_eq!(
+            vec![18, 12, 11],
+            sample_tree().into_iter().rev().take(3).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn iterate_degenerate_tree() {
+        let tree = degenerate_tree(200_000);
+
+        assert!(tree.iter().eq(0..200_000));
+        assert!(tree.iter().rev().eq((0..200_000).rev()));
+        assert!(tree.iter_preorder().eq(0..200_000));
+        assert!(tree.iter_postorder().eq((0..200_000).rev()));
+        assert!(tree.iter_level_order().eq(0..200_000));
+        assert!(tree.into_iter().eq(0..200_000));
+
+        drop(degenerate_tree(200_000));
+    }
+
+    #[test]
+    fn drop_partly_consumed_into_iter() {
+        let mut iter = degenerate_tree(200_000).into_iter();
+        assert_eq!(Some(0), iter.next());
+        assert_eq!(Some(199_999), iter.next_back());
+        drop(iter);
+
+        drop(degenerate_tree(200_000).into_iter());
+    }
+
+    fn tree_of(values: &[i32]) -> BinaryTree<i32> {
+        let mut tree = BinaryTree::default();
+
+        for value in values {
+            tree.insert(*value);
+        }
+
+        tree
+    }
+
+    #[test]
+    fn set_algebra_iterators() {
+        let a = tree_of(&[5, 1, 9, 3, 7]);
+        let b = tree_of(&[4, 3, 8, 5, 10]);
+
+        assert_eq!(
+            vec![1, 3, 4, 5, 7, 8, 9, 10],
+            a.union(&b).collect::<Vec<_>>()
+        );
+        assert_eq!(vec![3, 5], a.intersection(&b).collect::<Vec<_>>());
+        assert_eq!(vec![1, 7, 9], a.difference(&b).collect::<Vec<_>>());
+        assert_eq!(vec![4, 8, 10], b.difference(&a).collect::<Vec<_>>());
+        assert_eq!(
+            vec![1, 4, 7, 8, 9, 10],
+            a.symmetric_difference(&b).collect::<Vec<_>>()
+        );
+
+        let empty = BinaryTree::default();
+        assert_eq!(5, a.union(&empty).count());
+        assert_eq!(0, a.intersection(&empty).count());
+        assert_eq!(0, empty.difference(&a).count());
+    }
+
+    #[test]
+    fn set_algebra_operators() {
+        let a = tree_of(&[5, 1, 9, 3, 7]);
+        let b = tree_of(&[4, 3, 8, 5, 10]);
+
+        let union = &a | &b;
+        assert_eq!(8, union.len());
+        assert_eq!(8, check_sizes(union.root.as_ref().unwrap()));
+        assert_eq!(
+            vec![1, 3, 4, 5, 7, 8, 9, 10],
+            union.iter().collect::<Vec<_>>()
+        );
+
+        assert_eq!(vec![3, 5], (&a & &b).iter().collect::<Vec<_>>());
+        assert_eq!(vec![1, 7, 9], (&a - &b).iter().collect::<Vec<_>>());
+        assert_eq!(
+            vec![1, 4, 7, 8, 9, 10],
+            (&a ^ &b).iter().collect::<Vec<_>>()
+        );
+
+        let disjoint = &a & &tree_of(&[2, 4]);
+        assert!(disjoint.is_empty());
+        assert!(disjoint.root.is_none());
+    }
+
+    #[test]
+    fn subset_and_superset() {
+        let a = tree_of(&[5, 1, 9, 3, 7]);
+        let b = tree_of(&[3, 9]);
+        let c = tree_of(&[3, 4]);
+
+        assert!(b.is_subset(&a));
+        assert!(a.is_superset(&b));
+        assert!(!c.is_subset(&a));
+        assert!(!a.is_subset(&b));
+        assert!(a.is_subset(&a));
+        assert!(BinaryTree::default().is_subset(&c));
+    }
+
+    /// Asserts that every child links back to its parent and that subtree
+    /// sizes match, returning the values in order.
+    fn check_links(tree: &BinaryTree<i32>) -> Vec<i32> {
+        if let Some(ref root) = tree.root {
+            assert!(root.borrow().parent.is_none());
+            assert_eq!(tree.len(), check_sizes(root));
+        }
+
+        let mut stack: Vec<_> = tree.root.clone().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let n = node.borrow();
+            for child in n.left.iter().chain(n.right.iter()) {
+                let parent = child.borrow().parent.as_ref().and_then(|p| p.upgrade());
+                assert!(parent.is_some_and(|p| Rc::ptr_eq(&p, &node)));
+                stack.push(child.clone());
+            }
+        }
+
+        tree.iter().collect()
+    }
+
+    #[test]
+    fn split_off_tree() {
+        for key in [0, 3, 6, 10, 11, 15, 19, 25] {
+            let mut left = tree_of(&[10, 5, 15, 3, 7, 12, 18, 1, 4, 6, 8, 11, 13, 17, 19]);
+            let right = left.split_off(&key);
+
+            let left_values = check_links(&left);
+            let right_values = check_links(&right);
+
+            assert!(left_values.iter().all(|v| *v < key));
+            assert!(right_values.iter().all(|v| *v >= key));
+            assert_eq!(15, left.len() + right.len());
+        }
+    }
+
+    #[test]
+    fn split_off_and_append_back() {
+        let values = [10, 5, 15, 3, 7, 12, 18, 1, 4, 6, 8, 11, 13, 17, 19];
+        let mut tree = tree_of(&values);
+        let mut right = tree.split_off(&12);
+
+        assert_eq!(vec![1, 3, 4, 5, 6, 7, 8, 10, 11], check_links(&tree));
+        assert_eq!(vec![12, 13, 15, 17, 18, 19], check_links(&right));
+
+        tree.append(&mut right);
+
+        assert!(right.is_empty());
+        assert!(right.root.is_none());
+        assert_eq!(15, tree.len());
+        assert_eq!(
+            vec![1, 3, 4, 5, 6, 7, 8, 10, 11, 12, 13, 15, 17, 18, 19],
+            check_links(&tree)
+        );
+        assert_eq!(Some(12), tree.select(9));
+    }
+
+    #[test]
+    fn append_edge_cases() {
+        let mut empty = BinaryTree::default();
+        let mut tree = tree_of(&[2, 1, 3]);
+
+        empty.append(&mut tree);
+        assert_eq!(vec![1, 2, 3], check_links(&empty));
+        assert!(tree.is_empty());
+
+        empty.append(&mut tree);
+        assert_eq!(3, empty.len());
+
+        let mut overlapping = tree_of(&[2, 9, 0]);
+        empty.append(&mut overlapping);
+        assert_eq!(vec![0, 1, 2, 3, 9], check_links(&empty));
+        assert!(overlapping.is_empty());
+    }
+
+    fn height(link: &LeafPtr<i32>) -> usize {
+        link.as_ref().map_or(0, |n| {
+            let n = n.borrow();
+            1 + height(&n.left).max(height(&n.right))
+        })
+    }
+
+    #[test]
+    fn from_sorted_iter_builds_balanced_tree() {
+        let tree = BinaryTree::from_sorted_iter(0..1023).unwrap();
+
+        assert_eq!(1023, tree.len());
+        assert_eq!(10, height(&tree.root));
+        assert_eq!((0..1023).collect::<Vec<_>>(), check_links(&tree));
+        assert_eq!(Some(500), tree.select(500));
+
+        let empty = BinaryTree::<i32>::from_sorted_iter([]).unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn from_sorted_iter_rejects_unsorted_input() {
+        let unsorted = BinaryTree::from_sorted_iter([1, 2, 5, 4]);
+        let duplicated = BinaryTree::from_sorted_iter([1, 2, 2]);
+
+        assert_eq!(
+            Some(UnsortedError {
+                index: 3,
+                previous: 5,
+                value: 4
+            }),
+            unsorted.err()
+        );
+        assert_eq!(
+            Some(UnsortedError {
+                index: 2,
+                previous: 2,
+                value: 2
+            }),
+            duplicated.err()
+        );
+    }
+
+    #[test]
+    fn collect_sorts_and_deduplicates() {
+        let tree: BinaryTree<i32> = [5, 3, 9, 3, 1, 5, 7].into_iter().collect();
+
+        assert_eq!(vec![1, 3, 5, 7, 9], check_links(&tree));
+        assert_eq!(3, height(&tree.root));
+    }
+
+    #[test]
+    fn cursor_walks_in_order() {
+        let mut tree = sample_tree();
+        let mut cursor = tree.cursor_front_mut();
+
+        let mut forward = Vec::new();
+        while let Some(value) = cursor.peek() {
+            forward.push(value);
+            cursor.move_next();
+        }
+        assert_eq!(vec![3, 4, 5, 9, 10, 11, 12, 18], forward);
+
+        // the ghost position wraps around in both directions
+        cursor.move_next();
+        assert_eq!(Some(3), cursor.peek());
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(Some(18), cursor.peek());
+
+        let cursor = tree.lower_bound_mut(6);
+        assert_eq!(Some(9), cursor.peek());
+        assert_eq!(Some(5), cursor.peek_prev());
+        assert_eq!(Some(10), cursor.peek_next());
+        assert_eq!(None, tree.lower_bound_mut(19).peek());
+    }
+
+    #[test]
+    fn cursor_removes_current() {
+        let mut tree = sample_tree();
+        let mut cursor = tree.lower_bound_mut(10);
+
+        // 10 is the root with two children, 11 and 12 have one each
+        assert_eq!(Some(10), cursor.remove_current());
+        assert_eq!(Some(11), cursor.peek());
+        assert_eq!(Some(11), cursor.remove_current());
+        assert_eq!(Some(12), cursor.remove_current());
+        assert_eq!(Some(18), cursor.peek());
+        assert_eq!(Some(9), cursor.peek_prev());
+
+        assert_eq!(Some(18), cursor.remove_current());
+        assert_eq!(None, cursor.peek());
+        assert_eq!(None, cursor.remove_current());
+
+        assert_eq!(vec![3, 4, 5, 9], check_links(&tree));
+    }
+
+    #[test]
+    fn cursor_inserts_checking_order() {
+        let mut tree = tree_of(&[10, 5, 15]);
+        let mut cursor = tree.lower_bound_mut(10);
+
+        assert!(!cursor.insert_after(15));
+        assert!(!cursor.insert_after(10));
+        assert!(!cursor.insert_before(4));
+        assert!(cursor.insert_after(12));
+        assert!(cursor.insert_after(11));
+        assert!(cursor.insert_before(7));
+        assert!(cursor.insert_before(8));
+        assert_eq!(Some(10), cursor.peek());
+        assert_eq!(Some(11), cursor.peek_next());
+        assert_eq!(Some(8), cursor.peek_prev());
+
+        // on the ghost position `insert_after` prepends and `insert_before`
+        // appends
+        cursor.move_prev();
+        cursor.move_prev();
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(None, cursor.peek());
+        assert!(!cursor.insert_after(6));
+        assert!(cursor.insert_after(1));
+        assert!(cursor.insert_before(20));
+
+        assert_eq!(vec![1, 5, 7, 8, 10, 11, 12, 15, 20], check_links(&tree));
+
+        let mut empty = BinaryTree::default();
+        assert!(empty.cursor_front_mut().insert_after(3));
+        assert_eq!(vec![3], check_links(&empty));
+    }
+
+    #[test]
+    fn duplicate_insert_is_rejected() {
+        let mut tree = sample_tree();
+
+        assert!(!tree.insert(10));
+        assert!(!tree.insert(5));
+        assert!(tree.insert(6));
+        assert!(!tree.insert(6));
+
+        assert_eq!(9, tree.len());
+        assert_eq!(vec![3, 4, 5, 6, 9, 10, 11, 12, 18], check_links(&tree));
+    }
+
+    #[test]
+    fn reverse_comparator() {
+        let mut tree = BinaryTree::with_comparator(Reverse(Natural));
+        for value in [10, 9, 11, 12, 18, 4, 3, 5] {
+            assert!(tree.insert(value));
+        }
+        assert!(!tree.insert(12));
+
+        let root = tree.root.clone().unwrap();
+        assert_eq!(18, tree.min(root.clone()).unwrap().borrow().value);
+        assert_eq!(3, tree.max(root).unwrap().borrow().value);
+        assert_eq!(
+            vec![18, 12, 11, 10, 9, 5, 4, 3],
+            tree.iter().collect::<Vec<_>>()
+        );
+
+        assert!(tree.delete(10));
+        assert!(!tree.search(10));
+        assert!(tree.find(11).is_some());
+        assert_eq!(
+            vec![12, 11, 9, 5],
+            tree.range((Bound::Included(12), Bound::Included(5)))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(18), tree.select(0));
+        assert_eq!(2, tree.rank(&11));
+
+        let low = tree.split_off(&5);
+        assert_eq!(vec![18, 12, 11, 9], tree.iter().collect::<Vec<_>>());
+        assert_eq!(vec![5, 4, 3], low.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn key_extractor_comparator() {
+        #[derive(Debug, Clone, Copy)]
+        struct Employee {
+            id: u32,
+            age: u32,
+        }
+
+        let employee = |id, age| Employee { id, age };
+        let mut tree = BinaryTree::with_comparator(ByKey(|e: &Employee| e.age));
+
+        assert!(tree.insert(employee(1, 40)));
+        assert!(tree.insert(employee(2, 25)));
+        assert!(tree.insert(employee(3, 33)));
+        // the key decides equality, so a second employee aged 25 is rejected
+        assert!(!tree.insert(employee(4, 25)));
+
+        let ids: Vec<u32> = tree.iter().map(|e| e.id).collect();
+        assert_eq!(vec![2, 3, 1], ids);
+
+        let found = tree.find(employee(0, 33)).unwrap();
+        assert_eq!(3, found.borrow().value.id);
+        assert!(tree.delete(employee(0, 40)));
+        assert_eq!(2, tree.len());
+    }
+
+    #[test]
+    fn closure_comparator() {
+        let mut tree = BinaryTree::with_comparator(|a: &&str, b: &&str| {
+            a.to_lowercase().cmp(&b.to_lowercase())
+        });
+
+        for word in ["pear", "Apple", "fig", "APPLE", "Fig", "banana"] {
+            tree.insert(word);
+        }
+
+        assert_eq!(
+            vec!["Apple", "banana", "fig", "pear"],
+            tree.iter().collect::<Vec<_>>()
+        );
+        assert!(tree.search("PEAR"));
+
+        let mut cursor = tree.lower_bound_mut("B");
+        assert_eq!(Some("banana"), cursor.peek());
+        assert!(cursor.insert_after("cherry"));
+        assert!(!cursor.insert_after("FIG"));
+        assert_eq!(5, tree.len());
+    }
+
+    #[test]
+    fn render_views() {
+        let mut tree = BinaryTree::new(8);
+        tree.insert(3);
+        tree.insert(10);
+        tree.insert(5);
+
+        let options = RenderOptions {
+            parent_links: true,
+            metadata: true,
+        };
+
+        let expected = concat!(
+            "8 [size=4 height=3]\n",
+            "├── L: 3 [size=2 height=2] ↑8\n",
+            "│   └── R: 5 [size=1 height=1] ↑3\n",
+            "└── R: 10 [size=1 height=1] ↑8\n",
+        );
+        assert_eq!(expected, tree.to_ascii(&options));
+
+        let dot = tree.to_dot(&options);
+        assert!(dot.contains("n1 -> n2 [label=\"R\"];"));
+        assert!(dot.contains("n2 -> n1 [style=dashed, color=gray];"));
+        assert!(!dot.contains("dangling"));
+    }
 }
@@ -0,0 +1,66 @@
+use std::cmp::Ordering;
+
+/// Decides how a tree orders its values. Trees never compare values directly,
+/// so any total order can be plugged in: the natural one, its reverse, an
+/// order on a projected key, or a plain closure.
+pub trait Comparator<V> {
+    fn compare(&self, a: &V, b: &V) -> Ordering;
+}
+
+/// Orders values by their own `PartialOrd` implementation. Incomparable
+/// values (such as `NaN`) are treated as equal.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Natural;
+
+/// Flips the order of the wrapped comparator.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Reverse<C = Natural>(pub C);
+
+/// Orders values by the key that `F` extracts from them.
+#[derive(Debug, Clone, Copy)]
+pub struct ByKey<F>(pub F);
+
+impl<V: PartialOrd> Comparator<V> for Natural {
+    fn compare(&self, a: &V, b: &V) -> Ordering {
+        a.partial_cmp(b).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl<V, C: Comparator<V>> Comparator<V> for Reverse<C> {
+    fn compare(&self, a: &V, b: &V) -> Ordering {
+        self.0.compare(b, a)
+    }
+}
+
+impl<V, K: Ord, F: Fn(&V) -> K> Comparator<V> for ByKey<F> {
+    fn compare(&self, a: &V, b: &V) -> Ordering {
+        (self.0)(a).cmp(&(self.0)(b))
+    }
+}
+
+impl<V, F: Fn(&V, &V) -> Ordering> Comparator<V> for F {
+    fn compare(&self, a: &V, b: &V) -> Ordering {
+        self(a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ByKey, Comparator, Natural, Reverse};
+    use std::cmp::Ordering;
+
+    #[test]
+    fn ready_made_comparators() {
+        assert_eq!(Ordering::Less, Natural.compare(&1, &2));
+        assert_eq!(Ordering::Greater, Reverse(Natural).compare(&1, &2));
+        assert_eq!(Ordering::Less, Reverse(Reverse(Natural)).compare(&1, &2));
+        assert_eq!(Ordering::Equal, Natural.compare(&f64::NAN, &1.0));
+
+        let by_len = ByKey(|s: &&str| s.len());
+        assert_eq!(Ordering::Greater, by_len.compare(&"pear", &"fig"));
+        assert_eq!(Ordering::Equal, by_len.compare(&"fig", &"yew"));
+
+        let by_abs = |a: &i32, b: &i32| a.abs().cmp(&b.abs());
+        assert_eq!(Ordering::Less, by_abs.compare(&-1, &2));
+    }
+}
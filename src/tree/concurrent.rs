@@ -0,0 +1,619 @@
+use std::{
+    cmp::Ordering,
+    collections::hash_map::RandomState,
+    fmt::Debug,
+    hash::{BuildHasher, Hasher},
+    mem,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering as MemoryOrdering},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+};
+
+use super::comparator::{Comparator, Natural};
+
+/// A child pointer together with the lock that guards it. Locking a link
+/// grants access to the pointer, not to the node behind it; node values never
+/// change once a node is linked in.
+///
+/// The lock is hand-rolled rather than a `std::sync::RwLock` because its
+/// guards own a handle to the link instead of borrowing it. A descent can
+/// then lock the next link and drop the node it came from in a plain loop.
+struct Link<V> {
+    state: Mutex<LinkState<V>>,
+    released: Condvar,
+}
+
+struct LinkState<V> {
+    target: Option<Arc<Node<V>>>,
+    readers: usize,
+    writer: bool,
+    /// Writers queued for the link. New readers wait behind them so a steady
+    /// stream of lookups cannot starve a writer.
+    waiting: usize,
+    /// Threads of either kind asleep on the condition variable, so releasing
+    /// an uncontended link skips the wake-up.
+    sleeping: usize,
+}
+
+/// Shared access to a link, released on drop.
+struct ReadGuard<V> {
+    link: Arc<Link<V>>,
+    target: Option<Arc<Node<V>>>,
+}
+
+/// Exclusive access to a link, released on drop.
+struct WriteGuard<V> {
+    link: Arc<Link<V>>,
+}
+
+struct Node<V> {
+    value: V,
+    priority: u64,
+    left: Arc<Link<V>>,
+    right: Arc<Link<V>>,
+}
+
+/// Binary search tree that can be shared between threads.
+///
+/// Every link has its own lock and operations descend with hand-over-hand
+/// locking: the next link is locked before the current one is released.
+/// Lookups take read locks, so any number of them run side by side, while
+/// `insert` and `remove` take write locks and follow each other down the tree
+/// instead of serializing on the root. Since nobody can overtake a thread on
+/// the path they share, a lookup never misses a value that a concurrent
+/// `remove` is only moving.
+///
+/// The tree is a treap: nodes carry a random priority and are heap-ordered by
+/// it, which keeps the expected depth at `O(log n)` whatever the insertion
+/// order. Both updates work top-down, an insert splitting the subtree its
+/// node takes over and a remove merging the two subtrees left behind, so the
+/// locks are still only ever taken from the root downwards.
+pub struct ConcurrentTree<V, C = Natural> {
+    root: Arc<Link<V>>,
+    length: AtomicUsize,
+    /// Splitmix64 state for node priorities.
+    seed: AtomicU64,
+    comparator: C,
+}
+
+impl<V> Link<V> {
+    fn new(target: Option<Arc<Node<V>>>) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(LinkState {
+                target,
+                readers: 0,
+                writer: false,
+                waiting: 0,
+                sleeping: 0,
+            }),
+            released: Condvar::new(),
+        })
+    }
+
+    fn state(&self) -> MutexGuard<'_, LinkState<V>> {
+        self.state
+            .lock()
+            .expect("a thread panicked while updating a tree lock")
+    }
+
+    fn wait<'a>(&self, mut state: MutexGuard<'a, LinkState<V>>) -> MutexGuard<'a, LinkState<V>> {
+        state.sleeping += 1;
+        let mut state = self
+            .released
+            .wait(state)
+            .expect("a thread panicked while updating a tree lock");
+        state.sleeping -= 1;
+        state
+    }
+
+    /// Takes the pointer out of a link nobody else can reach any more.
+    fn into_target(mut link: Arc<Self>) -> Option<Arc<Node<V>>> {
+        let link = Arc::get_mut(&mut link)?;
+        let state = link.state.get_mut().unwrap_or_else(|e| e.into_inner());
+        state.target.take()
+    }
+}
+
+fn read<V>(link: &Arc<Link<V>>) -> ReadGuard<V> {
+    let mut state = link.state();
+    while state.writer || state.waiting > 0 {
+        state = link.wait(state);
+    }
+    state.readers += 1;
+    let target = state.target.clone();
+    drop(state);
+
+    ReadGuard {
+        link: link.clone(),
+        target,
+    }
+}
+
+fn write<V>(link: &Arc<Link<V>>) -> WriteGuard<V> {
+    let mut state = link.state();
+    state.waiting += 1;
+    while state.writer || state.readers > 0 {
+        state = link.wait(state);
+    }
+    state.waiting -= 1;
+    state.writer = true;
+    drop(state);
+
+    WriteGuard { link: link.clone() }
+}
+
+impl<V> ReadGuard<V> {
+    fn get(&self) -> Option<Arc<Node<V>>> {
+        self.target.clone()
+    }
+}
+
+impl<V> Drop for ReadGuard<V> {
+    fn drop(&mut self) {
+        let mut state = self.link.state();
+        state.readers -= 1;
+        if state.readers == 0 && state.sleeping > 0 {
+            self.link.released.notify_all();
+        }
+    }
+}
+
+impl<V> WriteGuard<V> {
+    fn get(&self) -> Option<Arc<Node<V>>> {
+        self.link.state().target.clone()
+    }
+
+    fn set(&self, target: Option<Arc<Node<V>>>) {
+        // the old target is dropped outside the state lock
+        let _old = mem::replace(&mut self.link.state().target, target);
+    }
+
+    fn take(&self) -> Option<Arc<Node<V>>> {
+        self.link.state().target.take()
+    }
+}
+
+impl<V> Drop for WriteGuard<V> {
+    fn drop(&mut self) {
+        let mut state = self.link.state();
+        state.writer = false;
+        if state.sleeping > 0 {
+            self.link.released.notify_all();
+        }
+    }
+}
+
+impl<V> Node<V> {
+    fn new(value: V, priority: u64) -> Self {
+        Self {
+            value,
+            priority,
+            left: Link::new(None),
+            right: Link::new(None),
+        }
+    }
+}
+
+impl<V, C: Default> Default for ConcurrentTree<V, C> {
+    fn default() -> Self {
+        Self::with_comparator(C::default())
+    }
+}
+
+impl<V: PartialOrd> ConcurrentTree<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<V, C> ConcurrentTree<V, C> {
+    pub fn with_comparator(comparator: C) -> Self {
+        Self {
+            root: Link::new(None),
+            length: AtomicUsize::new(0),
+            seed: AtomicU64::new(RandomState::new().build_hasher().finish()),
+            comparator,
+        }
+    }
+
+    /// Number of values. With writers active this is only a snapshot.
+    pub fn len(&self) -> usize {
+        self.length.load(MemoryOrdering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn priority(&self) -> u64 {
+        let mut z = self
+            .seed
+            .fetch_add(0x9E37_79B9_7F4A_7C15, MemoryOrdering::Relaxed);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Removes every value, unlinking the nodes one at a time so no subtree
+    /// is ever dropped recursively.
+    pub fn clear(&mut self) {
+        let root = mem::replace(&mut self.root, Link::new(None));
+        let mut stack: Vec<_> = Link::into_target(root).into_iter().collect();
+        *self.length.get_mut() = 0;
+
+        while let Some(node) = stack.pop() {
+            if let Some(node) = Arc::into_inner(node) {
+                stack.extend(Link::into_target(node.left));
+                stack.extend(Link::into_target(node.right));
+            }
+        }
+    }
+}
+
+impl<V, C: Comparator<V>> ConcurrentTree<V, C> {
+    pub fn contains(&self, value: &V) -> bool {
+        self.find(read(&self.root), value)
+    }
+
+    /// Looks for `value` below the link behind `guard`.
+    fn find(&self, mut guard: ReadGuard<V>, value: &V) -> bool {
+        while let Some(node) = guard.get() {
+            guard = match self.comparator.compare(value, &node.value) {
+                Ordering::Equal => return true,
+                Ordering::Less => read(&node.left),
+                Ordering::Greater => read(&node.right),
+            };
+        }
+
+        false
+    }
+
+    /// Returns `false`, leaving the tree unchanged, if `value` is already
+    /// present.
+    pub fn insert(&self, value: V) -> bool {
+        let inserted = self.insert_node(Node::new(value, self.priority()));
+        if inserted {
+            self.length.fetch_add(1, MemoryOrdering::Relaxed);
+        }
+        inserted
+    }
+
+    /// Walks down to the first node `new` outranks and takes over its place,
+    /// splitting the subtree found there between its two children.
+    fn insert_node(&self, new: Node<V>) -> bool {
+        let mut slot = write(&self.root);
+
+        let mut pending = loop {
+            let Some(node) = slot.get() else {
+                break None;
+            };
+            let order = self.comparator.compare(&new.value, &node.value);
+            if order.is_eq() {
+                return false;
+            }
+            let next = match order {
+                Ordering::Less => &node.left,
+                _ => &node.right,
+            };
+            if new.priority > node.priority {
+                // with `slot` locked nobody can add the value below, and
+                // anyone adding it further down is ahead of this lookup
+                if self.find(read(next), &new.value) {
+                    return false;
+                }
+                break slot.take();
+            }
+            slot = write(next);
+        };
+
+        // `slot` stays locked while the subtree is split, so nobody can
+        // reach the new node before it is complete
+        let mut left = write(&new.left);
+        let mut right = write(&new.right);
+
+        while let Some(node) = pending {
+            match self.comparator.compare(&new.value, &node.value) {
+                Ordering::Greater => {
+                    let next = write(&node.right);
+                    pending = next.take();
+                    left.set(Some(node));
+                    left = next;
+                }
+                _ => {
+                    let next = write(&node.left);
+                    pending = next.take();
+                    right.set(Some(node));
+                    right = next;
+                }
+            }
+        }
+
+        drop((left, right));
+        slot.set(Some(Arc::new(new)));
+        true
+    }
+
+    /// Removes `value`, returning whether it was present. The node's two
+    /// subtrees are merged into its place.
+    pub fn remove(&self, value: &V) -> bool {
+        let mut slot = write(&self.root);
+
+        let node = loop {
+            let Some(node) = slot.get() else {
+                return false;
+            };
+            slot = match self.comparator.compare(value, &node.value) {
+                Ordering::Equal => break node,
+                Ordering::Less => write(&node.left),
+                Ordering::Greater => write(&node.right),
+            };
+        };
+
+        let mut left = write(&node.left).take();
+        let mut right = write(&node.right).take();
+        slot.take();
+
+        // the root with the higher priority fills the hole, and the hole
+        // moves down to its inner child
+        loop {
+            match (left, right) {
+                (Some(l), Some(r)) if l.priority > r.priority => {
+                    let next = write(&l.right);
+                    (left, right) = (next.take(), Some(r));
+                    slot.set(Some(l));
+                    slot = next;
+                }
+                (Some(l), Some(r)) => {
+                    let next = write(&r.left);
+                    (left, right) = (Some(l), next.take());
+                    slot.set(Some(r));
+                    slot = next;
+                }
+                (rest, None) | (None, rest) => {
+                    slot.set(rest);
+                    break;
+                }
+            }
+        }
+
+        self.length.fetch_sub(1, MemoryOrdering::Relaxed);
+        true
+    }
+}
+
+impl<V: Clone, C> ConcurrentTree<V, C> {
+    /// In-order copy of the values. With writers active it is not a snapshot
+    /// of any single moment.
+    pub fn to_vec(&self) -> Vec<V> {
+        let mut values = Vec::with_capacity(self.len());
+        let mut stack = Vec::new();
+        let mut current = read(&self.root).get();
+
+        loop {
+            while let Some(node) = current {
+                current = read(&node.left).get();
+                stack.push(node);
+            }
+            let Some(node) = stack.pop() else {
+                break;
+            };
+            values.push(node.value.clone());
+            current = read(&node.right).get();
+        }
+
+        values
+    }
+}
+
+impl<V: Debug, C: Comparator<V>> ConcurrentTree<V, C> {
+    /// Checks that values are strictly ordered, that no child outranks its
+    /// parent and that the length matches the number of nodes. Only
+    /// meaningful while no writer is active.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut stack = Vec::new();
+        if let Some(root) = read(&self.root).get() {
+            stack.push((root, None::<Arc<Node<V>>>, None::<Arc<Node<V>>>));
+        }
+        let mut count = 0;
+
+        while let Some((node, lower, upper)) = stack.pop() {
+            count += 1;
+
+            let below = lower
+                .as_ref()
+                .is_some_and(|l| self.comparator.compare(&node.value, &l.value).is_le());
+            let above = upper
+                .as_ref()
+                .is_some_and(|u| self.comparator.compare(&node.value, &u.value).is_ge());
+            if below || above {
+                return Err(format!("{:?} is out of order", node.value));
+            }
+
+            let (left, right) = (read(&node.left).get(), read(&node.right).get());
+            if let Some(child) = left
+                .iter()
+                .chain(&right)
+                .find(|c| c.priority > node.priority)
+            {
+                return Err(format!(
+                    "{:?} outranks its parent {:?}",
+                    child.value, node.value
+                ));
+            }
+
+            if let Some(left) = left {
+                stack.push((left, lower, Some(node.clone())));
+            }
+            if let Some(right) = right {
+                stack.push((right, Some(node.clone()), upper));
+            }
+        }
+
+        if count != self.len() {
+            return Err(format!(
+                "tree holds {} nodes but length is {}",
+                count,
+                self.len()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl<V, C> Drop for ConcurrentTree<V, C> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        sync::atomic::{AtomicBool, Ordering},
+        thread,
+    };
+
+    use super::ConcurrentTree;
+    use crate::tree::{
+        comparator::{Natural, Reverse},
+        test_util::XorShift,
+    };
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn is_send_and_sync() {
+        assert_send_sync::<ConcurrentTree<u64>>();
+        assert_send_sync::<ConcurrentTree<String, Reverse>>();
+    }
+
+    #[test]
+    fn single_threaded() {
+        let tree = ConcurrentTree::new();
+        for value in [8, 3, 10, 1, 6, 14, 4, 7, 13] {
+            assert!(tree.insert(value));
+        }
+        assert!(!tree.insert(6));
+        assert_eq!(9, tree.len());
+
+        // leaf, one child and two children
+        assert!(tree.remove(&13));
+        assert!(tree.remove(&10));
+        assert!(tree.remove(&3));
+        assert!(tree.remove(&8));
+        assert!(!tree.remove(&8));
+
+        assert!(tree.contains(&6));
+        assert!(!tree.contains(&3));
+        assert_eq!(vec![1, 4, 6, 7, 14], tree.to_vec());
+        assert!(tree.validate().is_ok());
+
+        let reversed = ConcurrentTree::with_comparator(Reverse(Natural));
+        for value in 0..5 {
+            reversed.insert(value);
+        }
+        assert_eq!(vec![4, 3, 2, 1, 0], reversed.to_vec());
+    }
+
+    /// Sorted input would degenerate an unbalanced tree into a list. Runs on a
+    /// spawned thread, whose stack is smaller than the main test thread's.
+    #[test]
+    fn sorted_input_on_worker_thread() {
+        const COUNT: u64 = 100_000;
+
+        thread::spawn(|| {
+            let tree = ConcurrentTree::new();
+            for value in 0..COUNT {
+                assert!(tree.insert(value));
+            }
+            assert!(tree.validate().is_ok());
+
+            for value in 0..COUNT {
+                assert!(tree.contains(&value));
+            }
+            for value in 0..COUNT {
+                assert!(tree.remove(&value));
+            }
+            assert!(tree.is_empty());
+            assert!(tree.validate().is_ok());
+
+            // dropped while full
+            for value in (0..COUNT).rev() {
+                tree.insert(value);
+            }
+        })
+        .join()
+        .unwrap();
+    }
+
+    /// Writers each own the values congruent to their id and check every
+    /// result against a private model, while readers keep looking up values
+    /// that are never removed and values that are never inserted.
+    #[test]
+    fn stress() {
+        const WRITERS: u64 = 4;
+        const READERS: usize = 4;
+        const OPERATIONS: usize = 5_000;
+        const DOMAIN: u64 = 1_024;
+
+        let tree = ConcurrentTree::new();
+        // writers use multiples of 4 and the stable values sit between them,
+        // so removals keep moving successors across the stable values
+        let stable: Vec<u64> = (0..DOMAIN * WRITERS)
+            .step_by(7)
+            .map(|v| v * 4 + 2)
+            .collect();
+        for &value in &stable {
+            tree.insert(value);
+        }
+        let done = AtomicBool::new(false);
+
+        let models = thread::scope(|scope| {
+            for reader in 0..READERS {
+                let (tree, stable, done) = (&tree, &stable, &done);
+                scope.spawn(move || {
+                    let mut rng = XorShift::new(100 + reader as u64);
+                    while !done.load(Ordering::Relaxed) {
+                        let value = stable[rng.next_u64() as usize % stable.len()];
+                        assert!(tree.contains(&value), "lost {value}");
+                        assert!(!tree.contains(&(value + 1)));
+                    }
+                });
+            }
+
+            let writers: Vec<_> = (0..WRITERS)
+                .map(|id| {
+                    let tree = &tree;
+                    scope.spawn(move || {
+                        let mut rng = XorShift::new(id + 1);
+                        let mut model = BTreeSet::new();
+                        for _ in 0..OPERATIONS {
+                            let value = (rng.next_u64() % DOMAIN * WRITERS + id) * 4;
+                            if rng.next_u64() % 5 < 3 {
+                                assert_eq!(model.insert(value), tree.insert(value));
+                            } else {
+                                assert_eq!(model.remove(&value), tree.remove(&value));
+                            }
+                        }
+                        model
+                    })
+                })
+                .collect();
+
+            let models: Vec<_> = writers.into_iter().map(|w| w.join().unwrap()).collect();
+            done.store(true, Ordering::Relaxed);
+            models
+        });
+
+        let mut expected: BTreeSet<u64> = stable.into_iter().collect();
+        for model in models {
+            expected.extend(model);
+        }
+
+        assert!(tree.validate().is_ok());
+        assert_eq!(expected.into_iter().collect::<Vec<_>>(), tree.to_vec());
+    }
+}
@@ -0,0 +1,533 @@
+//! Randomized differential tests: every tree is driven through long random
+//! operation sequences alongside a `std` collection, and the two must agree
+//! after every step while `validate` keeps passing.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::tree::{
+    arena::ArenaTree,
+    avl::AvlTree,
+    binary::BinaryTree,
+    concurrent::ConcurrentTree,
+    interval::{Interval, IntervalTree},
+    map::TreeMap,
+    multiset::TreeMultiSet,
+    persistent::PersistentTree,
+    red_black::RedBlackTree,
+    tenary::TenaryTree,
+    test_util::XorShift,
+    treap::{ImplicitTreap, Treap},
+    tries::Trie,
+};
+
+const OPERATIONS: usize = 5_000;
+const DOMAIN: u64 = 256;
+const VALIDATE_EVERY: usize = 97;
+
+/// The slice of an ordered set's behaviour shared by every tree, so one
+/// driver can exercise them all.
+trait SetLike {
+    fn insert(&mut self, value: u64) -> bool;
+    fn remove(&mut self, value: u64) -> bool;
+    fn contains(&self, value: u64) -> bool;
+    fn len(&self) -> usize;
+    fn validate(&self) -> Result<(), String>;
+}
+
+impl SetLike for BinaryTree<u64> {
+    fn insert(&mut self, value: u64) -> bool {
+        BinaryTree::insert(self, value)
+    }
+
+    fn remove(&mut self, value: u64) -> bool {
+        self.delete(value)
+    }
+
+    fn contains(&self, value: u64) -> bool {
+        self.search(value)
+    }
+
+    fn len(&self) -> usize {
+        BinaryTree::len(self)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        BinaryTree::validate(self)
+    }
+}
+
+impl SetLike for AvlTree<u64> {
+    fn insert(&mut self, value: u64) -> bool {
+        AvlTree::insert(self, value)
+    }
+
+    fn remove(&mut self, value: u64) -> bool {
+        self.delete(value)
+    }
+
+    fn contains(&self, value: u64) -> bool {
+        self.search(value)
+    }
+
+    fn len(&self) -> usize {
+        AvlTree::len(self)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        AvlTree::validate(self)
+    }
+}
+
+impl SetLike for RedBlackTree<u64> {
+    fn insert(&mut self, value: u64) -> bool {
+        RedBlackTree::insert(self, value)
+    }
+
+    fn remove(&mut self, value: u64) -> bool {
+        self.delete(value)
+    }
+
+    fn contains(&self, value: u64) -> bool {
+        self.search(value)
+    }
+
+    fn len(&self) -> usize {
+        RedBlackTree::len(self)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        RedBlackTree::validate(self).map(|_| ())
+    }
+}
+
+impl SetLike for ArenaTree<u64> {
+    fn insert(&mut self, value: u64) -> bool {
+        ArenaTree::insert(self, value)
+    }
+
+    fn remove(&mut self, value: u64) -> bool {
+        self.delete(value)
+    }
+
+    fn contains(&self, value: u64) -> bool {
+        self.search(value)
+    }
+
+    fn len(&self) -> usize {
+        ArenaTree::len(self)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        ArenaTree::validate(self)
+    }
+}
+
+impl SetLike for Treap<u64> {
+    fn insert(&mut self, value: u64) -> bool {
+        Treap::insert(self, value)
+    }
+
+    fn remove(&mut self, value: u64) -> bool {
+        Treap::remove(self, &value)
+    }
+
+    fn contains(&self, value: u64) -> bool {
+        Treap::contains(self, &value)
+    }
+
+    fn len(&self) -> usize {
+        Treap::len(self)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        Treap::validate(self)
+    }
+}
+
+impl SetLike for PersistentTree<u64> {
+    fn insert(&mut self, value: u64) -> bool {
+        let next = PersistentTree::insert(self, value);
+        let changed = !next.ptr_eq(self);
+        *self = next;
+        changed
+    }
+
+    fn remove(&mut self, value: u64) -> bool {
+        let next = PersistentTree::remove(self, &value);
+        let changed = !next.ptr_eq(self);
+        *self = next;
+        changed
+    }
+
+    fn contains(&self, value: u64) -> bool {
+        PersistentTree::contains(self, &value)
+    }
+
+    fn len(&self) -> usize {
+        PersistentTree::len(self)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        PersistentTree::validate(self)
+    }
+}
+
+impl SetLike for ConcurrentTree<u64> {
+    fn insert(&mut self, value: u64) -> bool {
+        ConcurrentTree::insert(self, value)
+    }
+
+    fn remove(&mut self, value: u64) -> bool {
+        ConcurrentTree::remove(self, &value)
+    }
+
+    fn contains(&self, value: u64) -> bool {
+        ConcurrentTree::contains(self, &value)
+    }
+
+    fn len(&self) -> usize {
+        ConcurrentTree::len(self)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        ConcurrentTree::validate(self)
+    }
+}
+
+fn check_set<S: SetLike>(set: &S, model: &BTreeSet<u64>) {
+    assert_eq!(model.len(), set.len());
+    for value in 0..DOMAIN {
+        assert_eq!(
+            model.contains(&value),
+            set.contains(value),
+            "membership of {value}"
+        );
+    }
+}
+
+/// Runs a random mix of inserts and removals against `set` and a
+/// `BTreeSet`. The tree may already hold values, which are mirrored into the
+/// model first.
+fn drive<S: SetLike>(mut set: S, seeded: &[u64], seed: u64) {
+    let mut rng = XorShift::new(seed);
+    let mut model: BTreeSet<u64> = seeded.iter().copied().collect();
+
+    for step in 0..OPERATIONS {
+        let value = rng.next_u64() % DOMAIN;
+        // Lean towards inserts so the trees grow deep before shrinking.
+        if rng.next_u64() % 5 < 3 {
+            assert_eq!(
+                model.insert(value),
+                set.insert(value),
+                "insert {value} at step {step}"
+            );
+        } else {
+            assert_eq!(
+                model.remove(&value),
+                set.remove(value),
+                "remove {value} at step {step}"
+            );
+        }
+
+        assert_eq!(model.len(), set.len());
+        if step % VALIDATE_EVERY == 0 {
+            set.validate()
+                .unwrap_or_else(|e| panic!("step {step}: {e}"));
+            check_set(&set, &model);
+        }
+    }
+
+    // Drain to empty, seeded values included; those are only there because
+    // some constructors take a first value.
+    for value in model.clone() {
+        assert!(set.remove(value), "drain {value}");
+        model.remove(&value);
+    }
+    assert_eq!(0, set.len());
+    set.validate().unwrap();
+    check_set(&set, &model);
+}
+
+#[test]
+fn binary_matches_btree_set() {
+    drive(BinaryTree::<u64>::default(), &[], 1);
+}
+
+#[test]
+fn avl_matches_btree_set() {
+    drive(AvlTree::new(DOMAIN / 2), &[DOMAIN / 2], 2);
+}
+
+#[test]
+fn red_black_matches_btree_set() {
+    drive(RedBlackTree::new(DOMAIN / 2), &[DOMAIN / 2], 3);
+}
+
+#[test]
+fn arena_matches_btree_set() {
+    drive(ArenaTree::<u64>::default(), &[], 4);
+}
+
+#[test]
+fn treap_matches_btree_set() {
+    drive(Treap::<u64>::with_seed(5), &[], 6);
+}
+
+#[test]
+fn persistent_matches_btree_set() {
+    drive(PersistentTree::<u64>::new(), &[], 7);
+}
+
+#[test]
+fn concurrent_matches_btree_set() {
+    drive(ConcurrentTree::<u64>::new(), &[], 14);
+}
+
+#[test]
+fn persistent_snapshots_survive_later_edits() {
+    let mut rng = XorShift::new(8);
+    let mut tree = PersistentTree::new();
+    let mut model = BTreeSet::new();
+    let mut snapshots = Vec::new();
+
+    for step in 0..OPERATIONS {
+        let value = rng.next_u64() % DOMAIN;
+        if rng.next_u64().is_multiple_of(2) {
+            tree = tree.insert(value);
+            model.insert(value);
+        } else {
+            tree = tree.remove(&value);
+            model.remove(&value);
+        }
+
+        if step % 500 == 0 {
+            snapshots.push((tree.clone(), model.clone()));
+        }
+    }
+
+    for (snapshot, expected) in &snapshots {
+        snapshot.validate().unwrap();
+        assert_eq!(
+            expected.iter().copied().collect::<Vec<_>>(),
+            snapshot.iter().collect::<Vec<_>>()
+        );
+    }
+}
+
+#[test]
+fn tree_map_matches_btree_map() {
+    let mut rng = XorShift::new(9);
+    let mut map = TreeMap::new();
+    let mut model = BTreeMap::new();
+
+    for step in 0..OPERATIONS {
+        let key = rng.next_u64() % DOMAIN;
+        match rng.next_u64() % 3 {
+            0 | 1 => assert_eq!(model.insert(key, step), map.insert(key, step)),
+            _ => assert_eq!(model.remove(&key), map.remove(&key)),
+        }
+
+        assert_eq!(model.len(), map.len());
+        if step % VALIDATE_EVERY == 0 {
+            map.validate().unwrap();
+            assert!(model.iter().eq(map.iter()));
+        }
+    }
+
+    for key in 0..DOMAIN {
+        assert_eq!(model.get(&key), map.get(&key));
+    }
+}
+
+#[test]
+fn multiset_matches_btree_map_of_counts() {
+    let mut rng = XorShift::new(10);
+    let mut set = TreeMultiSet::new();
+    let mut model: BTreeMap<u64, usize> = BTreeMap::new();
+
+    for step in 0..OPERATIONS {
+        let value = rng.next_u64() % (DOMAIN / 4);
+        match rng.next_u64() % 4 {
+            0 | 1 => {
+                let count = model.entry(value).or_insert(0);
+                *count += 1;
+                assert_eq!(*count, set.insert(value));
+            }
+            2 => {
+                let present = match model.get_mut(&value) {
+                    Some(count) if *count > 1 => {
+                        *count -= 1;
+                        true
+                    }
+                    Some(_) => model.remove(&value).is_some(),
+                    None => false,
+                };
+                assert_eq!(present, set.remove_one(&value));
+            }
+            _ => assert_eq!(model.remove(&value).unwrap_or(0), set.remove_all(&value)),
+        }
+
+        assert_eq!(model.values().sum::<usize>(), set.len());
+        assert_eq!(model.len(), set.distinct_len());
+        if step % VALIDATE_EVERY == 0 {
+            set.validate().unwrap();
+            for value in 0..DOMAIN / 4 {
+                assert_eq!(model.get(&value).copied().unwrap_or(0), set.count(&value));
+            }
+        }
+    }
+}
+
+#[test]
+fn interval_tree_matches_btree_set() {
+    let mut rng = XorShift::new(11);
+    let mut tree = IntervalTree::new();
+    let mut model = BTreeSet::new();
+
+    for step in 0..OPERATIONS {
+        let start = rng.next_u64() % DOMAIN;
+        let interval = Interval::new(start, start + rng.next_u64() % 16);
+        if rng.next_u64() % 5 < 3 {
+            assert_eq!(model.insert(interval), tree.insert(interval));
+        } else {
+            assert_eq!(model.remove(&interval), tree.remove(&interval));
+        }
+
+        assert_eq!(model.len(), tree.len());
+        if step % VALIDATE_EVERY == 0 {
+            tree.validate().unwrap();
+
+            let point = rng.next_u64() % DOMAIN;
+            let mut stabbed = tree.stabbing(point).collect::<Vec<_>>();
+            stabbed.sort();
+            let expected = model
+                .iter()
+                .copied()
+                .filter(|i| i.contains(point))
+                .collect::<Vec<_>>();
+            assert_eq!(expected, stabbed);
+        }
+    }
+}
+
+#[test]
+fn implicit_treap_matches_vec() {
+    let mut rng = XorShift::new(12);
+    let mut treap = ImplicitTreap::with_seed(13);
+    let mut model = Vec::new();
+
+    for step in 0..OPERATIONS {
+        let len = model.len() as u64;
+        match rng.next_u64() % 4 {
+            0 | 1 => {
+                let index = (rng.next_u64() % (len + 1)) as usize;
+                model.insert(index, step);
+                treap.insert(index, step);
+            }
+            2 if len > 0 => {
+                let index = (rng.next_u64() % len) as usize;
+                assert_eq!(Some(model.remove(index)), treap.remove(index));
+            }
+            _ => {
+                let a = (rng.next_u64() % (len + 1)) as usize;
+                let b = (rng.next_u64() % (len + 1)) as usize;
+                let range = a.min(b)..a.max(b);
+                model[range.clone()].reverse();
+                treap.reverse(range);
+            }
+        }
+
+        assert_eq!(model.len(), treap.len());
+        if step % VALIDATE_EVERY == 0 {
+            treap.validate().unwrap();
+            assert!(model.iter().eq(treap.iter()));
+            let index = (rng.next_u64() % (len + 1)) as usize;
+            assert_eq!(model.get(index), treap.get(index));
+        }
+    }
+}
+
+/// Random word of up to five letters from a four-letter alphabet, so words
+/// share prefixes often. May be empty.
+fn word(rng: &mut XorShift) -> String {
+    let len = rng.next_u64() % 6;
+    (0..len)
+        .map(|_| char::from(b'a' + (rng.next_u64() % 4) as u8))
+        .collect()
+}
+
+#[test]
+fn trie_matches_btree_set() {
+    let mut rng = XorShift::new(15);
+    let mut trie = Trie::<u8>::new();
+    let mut model: BTreeSet<Vec<u8>> = BTreeSet::new();
+
+    for step in 0..OPERATIONS {
+        let key = word(&mut rng).into_bytes();
+        match rng.next_u64() % 64 {
+            0 => {
+                let before = model.len();
+                model.retain(|k| !k.starts_with(&key));
+                assert_eq!(
+                    before - model.len(),
+                    trie.remove_prefix(key.clone()),
+                    "remove prefix {key:?} at step {step}"
+                );
+            }
+            1..=39 => assert_eq!(
+                model.insert(key.clone()),
+                trie.insert(key.clone()),
+                "insert {key:?} at step {step}"
+            ),
+            _ => assert_eq!(
+                model.remove(&key),
+                trie.remove(key.clone()),
+                "remove {key:?} at step {step}"
+            ),
+        }
+
+        assert_eq!(model.len(), trie.len());
+        if step % VALIDATE_EVERY == 0 {
+            trie.validate()
+                .unwrap_or_else(|e| panic!("step {step}: {e}"));
+            assert!(model.iter().cloned().eq(trie.completions([])));
+        }
+    }
+
+    for key in model.clone() {
+        assert!(trie.remove(key.clone()), "drain {key:?}");
+        model.remove(&key);
+    }
+    assert!(trie.is_empty());
+    trie.validate().unwrap();
+}
+
+#[test]
+fn tenary_tree_matches_btree_set() {
+    let mut rng = XorShift::new(16);
+    let mut tree = TenaryTree::new();
+    let mut model = BTreeSet::new();
+
+    for step in 0..OPERATIONS {
+        let word = word(&mut rng);
+        // the empty word has no node to end on and is never stored
+        assert_eq!(
+            !word.is_empty() && model.insert(word.clone()),
+            tree.insert(&word),
+            "insert {word:?} at step {step}"
+        );
+
+        assert_eq!(model.len(), tree.len());
+        if step % VALIDATE_EVERY == 0 {
+            tree.validate()
+                .unwrap_or_else(|e| panic!("step {step}: {e}"));
+            for probe in (0..32).map(|_| self::word(&mut rng)) {
+                assert_eq!(model.contains(&probe), tree.contains(&probe), "{probe:?}");
+            }
+        }
+    }
+
+    for word in &model {
+        assert!(tree.contains(word), "lost {word:?}");
+    }
+}
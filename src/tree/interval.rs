@@ -0,0 +1,483 @@
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    fmt::Debug,
+    marker::PhantomData,
+    rc::{Rc, Weak},
+};
+
+use super::binary::{BackLink, Link};
+
+/// Closed interval `[start, end]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval<T> {
+    pub start: T,
+    pub end: T,
+}
+
+/// `tree::binary::Node` with the interval as the key and `max`, the largest
+/// endpoint anywhere in the subtree, as augmentation.
+#[derive(Debug, Clone)]
+pub struct Node<T> {
+    pub interval: Interval<T>,
+    pub max: T,
+
+    pub parent: BackLink<Node<T>>,
+    pub left: Link<Node<T>>,
+    pub right: Link<Node<T>>,
+}
+
+/// Binary search tree of intervals ordered by start, then end. The `max`
+/// augmentation lets overlap queries skip every subtree that ends before the
+/// query begins.
+pub struct IntervalTree<T> {
+    root: Link<Node<T>>,
+    length: usize,
+}
+
+/// In-order iterator over the intervals overlapping a query, returned by
+/// `IntervalTree::overlapping` and `IntervalTree::stabbing`.
+pub struct Overlapping<'a, T> {
+    stack: Vec<Rc<RefCell<Node<T>>>>,
+    query: Interval<T>,
+    _tree: PhantomData<&'a ()>,
+}
+
+impl<T: Ord + Copy + Debug> Interval<T> {
+    /// Panics when `start` is greater than `end`.
+    pub fn new(start: T, end: T) -> Self {
+        assert!(
+            start <= end,
+            "interval starts after it ends: {start:?} > {end:?}"
+        );
+        Self { start, end }
+    }
+
+    pub fn point(point: T) -> Self {
+        Self::new(point, point)
+    }
+
+    pub fn overlaps(&self, other: &Interval<T>) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub fn contains(&self, point: T) -> bool {
+        self.start <= point && point <= self.end
+    }
+}
+
+impl<T> From<Node<T>> for Link<Node<T>> {
+    fn from(value: Node<T>) -> Self {
+        Some(Rc::new(RefCell::new(value)))
+    }
+}
+
+impl<T: Copy> Node<T> {
+    fn new(interval: Interval<T>) -> Self {
+        Self {
+            interval,
+            max: interval.end,
+            parent: None,
+            left: None,
+            right: None,
+        }
+    }
+
+    fn with_parent(mut self, parent: &Rc<RefCell<Node<T>>>) -> Self {
+        self.parent = Some(Rc::downgrade(parent));
+        self
+    }
+}
+
+/// Recomputes `max` for `link` and every ancestor above it.
+fn update_max<T: Ord + Copy>(mut link: Link<Node<T>>) {
+    while let Some(node) = link {
+        let max = {
+            let n = node.borrow();
+            let children = n.left.iter().chain(n.right.iter());
+            children.fold(n.interval.end, |max, child| max.max(child.borrow().max))
+        };
+        node.borrow_mut().max = max;
+        link = node.borrow().parent.as_ref().and_then(Weak::upgrade);
+    }
+}
+
+impl<T> Default for IntervalTree<T> {
+    fn default() -> Self {
+        Self {
+            root: None,
+            length: 0,
+        }
+    }
+}
+
+impl<T: Ord + Copy + Debug> IntervalTree<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Inserts `interval`, returning `false` when the same interval is
+    /// already stored.
+    pub fn insert(&mut self, interval: Interval<T>) -> bool {
+        let mut cursor = match self.root.clone() {
+            Some(root) => root,
+            None => {
+                self.root = Node::new(interval).into();
+                self.length += 1;
+                return true;
+            }
+        };
+
+        loop {
+            let ordering = interval.cmp(&cursor.borrow().interval);
+            let child = match ordering {
+                Ordering::Equal => return false,
+                Ordering::Less => cursor.borrow().left.clone(),
+                Ordering::Greater => cursor.borrow().right.clone(),
+            };
+
+            match child {
+                Some(child) => cursor = child,
+                None => {
+                    let node = Node::new(interval).with_parent(&cursor).into();
+                    if ordering == Ordering::Less {
+                        cursor.borrow_mut().left = node;
+                    } else {
+                        cursor.borrow_mut().right = node;
+                    }
+                    break;
+                }
+            }
+        }
+
+        self.length += 1;
+        update_max(Some(cursor));
+        true
+    }
+
+    pub fn remove(&mut self, interval: &Interval<T>) -> bool {
+        let Some(target) = self.find(interval) else {
+            return false;
+        };
+
+        let has_both = {
+            let t = target.borrow();
+            t.left.is_some() && t.right.is_some()
+        };
+
+        // same as `BinaryTree`: a node with two children takes over the
+        // interval of its predecessor, which is spliced out instead
+        let removed = if has_both {
+            let mut predecessor = target.borrow().left.clone().unwrap();
+            loop {
+                let right = predecessor.borrow().right.clone();
+                match right {
+                    Some(r) => predecessor = r,
+                    None => break,
+                }
+            }
+            target.borrow_mut().interval = predecessor.borrow().interval;
+            predecessor
+        } else {
+            target
+        };
+
+        self.detach(&removed);
+        self.length -= 1;
+        true
+    }
+
+    /// Unlinks `node`, which has at most one child, moving that child into
+    /// its place and refreshing `max` above it.
+    fn detach(&mut self, node: &Rc<RefCell<Node<T>>>) {
+        let (parent, child) = {
+            let mut n = node.borrow_mut();
+            let child = n.left.take().or_else(|| n.right.take());
+            (n.parent.take().and_then(|p| p.upgrade()), child)
+        };
+
+        if let Some(ref c) = child {
+            c.borrow_mut().parent = parent.as_ref().map(Rc::downgrade);
+        }
+
+        match parent {
+            Some(ref p) => {
+                let is_left = p
+                    .borrow()
+                    .left
+                    .as_ref()
+                    .is_some_and(|l| Rc::ptr_eq(l, node));
+                if is_left {
+                    p.borrow_mut().left = child;
+                } else {
+                    p.borrow_mut().right = child;
+                }
+            }
+            None => self.root = child,
+        }
+
+        update_max(parent);
+    }
+
+    fn find(&self, interval: &Interval<T>) -> Link<Node<T>> {
+        let mut cursor = self.root.clone();
+
+        while let Some(node) = cursor {
+            let ordering = interval.cmp(&node.borrow().interval);
+            cursor = match ordering {
+                Ordering::Equal => return Some(node),
+                Ordering::Less => node.borrow().left.clone(),
+                Ordering::Greater => node.borrow().right.clone(),
+            };
+        }
+
+        None
+    }
+
+    pub fn contains(&self, interval: &Interval<T>) -> bool {
+        self.find(interval).is_some()
+    }
+
+    /// Checks that intervals are strictly ordered and well formed, that each
+    /// `max` is the largest endpoint of its subtree, that every child links
+    /// back to its parent and that the length matches the node count.
+    pub fn validate(&self) -> Result<(), String> {
+        let root = match self.root {
+            Some(ref root) => root,
+            None if self.length == 0 => return Ok(()),
+            None => return Err(format!("empty tree reports length {}", self.length)),
+        };
+
+        if root.borrow().parent.is_some() {
+            return Err("root has a parent".to_string());
+        }
+
+        // an explicit stack, since a degenerate tree is as deep as it is long
+        let mut stack = vec![(root.clone(), None, None)];
+        let mut count = 0;
+
+        while let Some((node, lower, upper)) = stack.pop() {
+            let n = node.borrow();
+            count += 1;
+
+            if n.interval.start > n.interval.end {
+                return Err(format!("{:?} starts after it ends", n.interval));
+            }
+            if lower.is_some_and(|l| n.interval <= l) || upper.is_some_and(|u| n.interval >= u) {
+                return Err(format!("{:?} is out of order", n.interval));
+            }
+
+            let children = n.left.iter().chain(n.right.iter());
+            let max = children.fold(n.interval.end, |max, child| max.max(child.borrow().max));
+            if n.max != max {
+                return Err(format!(
+                    "{:?} stores max {:?} but its subtree reaches {:?}",
+                    n.interval, n.max, max
+                ));
+            }
+
+            let children = [
+                (&n.left, lower, Some(n.interval)),
+                (&n.right, Some(n.interval), upper),
+            ];
+            for (child, lower, upper) in children {
+                let Some(c) = child else {
+                    continue;
+                };
+
+                let linked = c.borrow().parent.as_ref().and_then(Weak::upgrade);
+                if !linked.is_some_and(|p| Rc::ptr_eq(&p, &node)) {
+                    return Err(format!(
+                        "child of {:?} does not link back to it",
+                        n.interval
+                    ));
+                }
+                stack.push((c.clone(), lower, upper));
+            }
+        }
+
+        if count != self.length {
+            return Err(format!(
+                "tree holds {} nodes but length is {}",
+                count, self.length
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Every stored interval that shares at least one point with `query`,
+    /// ordered by start.
+    pub fn overlapping(&self, query: Interval<T>) -> Overlapping<'_, T> {
+        let mut iter = Overlapping {
+            stack: Vec::new(),
+            query,
+            _tree: PhantomData,
+        };
+        iter.descend(self.root.clone());
+        iter
+    }
+
+    /// Every stored interval that contains `point`.
+    pub fn stabbing(&self, point: T) -> Overlapping<'_, T> {
+        self.overlapping(Interval::point(point))
+    }
+}
+
+impl<T> Drop for IntervalTree<T> {
+    /// Unlinks the nodes one at a time so a degenerate tree does not
+    /// overflow the stack.
+    fn drop(&mut self) {
+        let mut stack: Vec<_> = self.root.take().into_iter().collect();
+
+        while let Some(node) = stack.pop() {
+            let mut n = node.borrow_mut();
+            stack.extend(n.left.take());
+            stack.extend(n.right.take());
+        }
+    }
+}
+
+impl<T: Ord + Copy + Debug> Overlapping<'_, T> {
+    /// Pushes the left spine of `link`, stopping at the first subtree whose
+    /// intervals all end before the query starts.
+    fn descend(&mut self, mut link: Link<Node<T>>) {
+        while let Some(node) = link {
+            if node.borrow().max < self.query.start {
+                break;
+            }
+            link = node.borrow().left.clone();
+            self.stack.push(node);
+        }
+    }
+}
+
+impl<T: Ord + Copy + Debug> Iterator for Overlapping<'_, T> {
+    type Item = Interval<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let node = self.stack.pop()?;
+            let interval = node.borrow().interval;
+
+            // intervals come out ordered by start, so once one starts after
+            // the query ends none of the remaining ones can overlap it
+            if interval.start > self.query.end {
+                self.stack.clear();
+                return None;
+            }
+
+            self.descend(node.borrow().right.clone());
+            if interval.overlaps(&self.query) {
+                return Some(interval);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::{Interval, IntervalTree, Node};
+    use crate::tree::test_util::XorShift;
+
+    /// Returns the real subtree maximum while asserting the stored one and
+    /// the parent links.
+    fn check_max(node: &Rc<RefCell<Node<i32>>>) -> i32 {
+        let n = node.borrow();
+        let mut max = n.interval.end;
+
+        for child in n.left.iter().chain(n.right.iter()) {
+            let parent = child.borrow().parent.as_ref().and_then(|p| p.upgrade());
+            assert!(parent.is_some_and(|p| Rc::ptr_eq(&p, node)));
+            max = max.max(check_max(child));
+        }
+
+        assert_eq!(max, n.max);
+        max
+    }
+
+    fn reservations() -> IntervalTree<i32> {
+        let mut tree = IntervalTree::new();
+        for (start, end) in [(15, 20), (10, 30), (17, 19), (5, 20), (12, 15), (30, 40)] {
+            assert!(tree.insert(Interval::new(start, end)));
+        }
+        tree
+    }
+
+    #[test]
+    fn overlap_and_stabbing_queries() {
+        let tree = reservations();
+        check_max(tree.root.as_ref().unwrap());
+
+        let found: Vec<_> = tree
+            .overlapping(Interval::new(18, 25))
+            .map(|i| (i.start, i.end))
+            .collect();
+        assert_eq!(vec![(5, 20), (10, 30), (15, 20), (17, 19)], found);
+
+        let found: Vec<_> = tree.stabbing(30).map(|i| (i.start, i.end)).collect();
+        assert_eq!(vec![(10, 30), (30, 40)], found);
+
+        assert_eq!(0, tree.stabbing(41).count());
+        assert_eq!(0, tree.overlapping(Interval::new(0, 4)).count());
+    }
+
+    #[test]
+    fn remove_keeps_max_up_to_date() {
+        let mut tree = reservations();
+
+        assert!(!tree.insert(Interval::new(10, 30)));
+        assert!(tree.remove(&Interval::new(10, 30)));
+        assert!(tree.remove(&Interval::new(30, 40)));
+        assert!(!tree.remove(&Interval::new(30, 40)));
+        assert!(!tree.contains(&Interval::new(10, 30)));
+
+        assert_eq!(4, tree.len());
+        assert_eq!(20, check_max(tree.root.as_ref().unwrap()));
+        assert_eq!(0, tree.stabbing(25).count());
+    }
+
+    #[test]
+    fn random_queries_match_linear_scan() {
+        let mut rng = XorShift::new(0x9E37_79B9_7F4A_7C15);
+        let mut tree = IntervalTree::new();
+        let mut expected: Vec<Interval<i32>> = Vec::new();
+
+        for _ in 0..2000 {
+            let start = (rng.next_u64() % 1000) as i32;
+            let interval = Interval::new(start, start + (rng.next_u64() % 50) as i32);
+
+            if rng.next_u64().is_multiple_of(3) && !expected.is_empty() {
+                let victim = expected.remove((rng.next_u64() % expected.len() as u64) as usize);
+                assert!(tree.remove(&victim));
+            } else if !expected.contains(&interval) {
+                assert!(tree.insert(interval));
+                expected.push(interval);
+            }
+        }
+
+        expected.sort();
+        check_max(tree.root.as_ref().unwrap());
+
+        for _ in 0..200 {
+            let start = (rng.next_u64() % 1000) as i32;
+            let query = Interval::new(start, start + (rng.next_u64() % 100) as i32);
+            let overlapping: Vec<_> = expected
+                .iter()
+                .filter(|i| i.overlaps(&query))
+                .copied()
+                .collect();
+            assert!(tree.overlapping(query).eq(overlapping));
+        }
+    }
+}
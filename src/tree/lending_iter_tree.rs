@@ -57,7 +57,7 @@ impl <T: Clone> Tree<T> {
     }
 }
 
-impl <'a, It: PartialOrd + Clone> LendingIterator for NodeIter<It> {
+impl <It: PartialOrd + Clone> LendingIterator for NodeIter<It> {
     type Item<'c> = &'c It
         where Self: 'c;
 
@@ -0,0 +1,488 @@
+use std::{cmp::Ordering, fmt::Debug, mem};
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+#[derive(Debug, Clone)]
+pub struct Node<K, V> {
+    pub key: K,
+    pub value: V,
+
+    pub left: Link<K, V>,
+    pub right: Link<K, V>,
+}
+
+/// Ordered map on an unbalanced binary search tree. Unlike `BinaryTree` it
+/// only needs `K: Ord`, and nodes own their children through `Box` so lookups
+/// can hand out plain `&V`/`&mut V` instead of `RefCell` guards.
+pub struct TreeMap<K, V> {
+    root: Link<K, V>,
+    length: usize,
+}
+
+/// View into a single key of a `TreeMap`, found with one descent from the
+/// root so a read-modify-write never walks the tree twice.
+pub enum Entry<'a, K, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+pub struct OccupiedEntry<'a, K, V> {
+    node: &'a mut Node<K, V>,
+}
+
+/// In-order iterator over the entries, returned by `TreeMap::iter`.
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+    remaining: usize,
+}
+
+/// Remembers the empty link the search ended on, so inserting is a single
+/// assignment.
+pub struct VacantEntry<'a, K, V> {
+    key: K,
+    slot: &'a mut Link<K, V>,
+    length: &'a mut usize,
+}
+
+impl<K, V> Node<K, V> {
+    fn new(key: K, value: V) -> Self {
+        Self {
+            key,
+            value,
+            left: None,
+            right: None,
+        }
+    }
+}
+
+impl<K, V> Default for TreeMap<K, V> {
+    fn default() -> Self {
+        Self {
+            root: None,
+            length: 0,
+        }
+    }
+}
+
+impl<K: Ord, V> TreeMap<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Inserts `value` under `key`, handing back the value it replaced.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let slot = Self::find_slot(&mut self.root, &key);
+
+        match slot {
+            Some(node) => Some(mem::replace(&mut node.value, value)),
+            None => {
+                *slot = Some(Box::new(Node::new(key, value)));
+                self.length += 1;
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut cursor = self.root.as_ref();
+
+        while let Some(node) = cursor {
+            cursor = match key.cmp(&node.key) {
+                Ordering::Less => node.left.as_ref(),
+                Ordering::Greater => node.right.as_ref(),
+                Ordering::Equal => return Some(&node.value),
+            };
+        }
+
+        None
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        Self::find_slot(&mut self.root, key)
+            .as_mut()
+            .map(|node| &mut node.value)
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        let slot = Self::find_slot(&mut self.root, &key);
+
+        if slot.is_some() {
+            Entry::Occupied(OccupiedEntry {
+                node: slot.as_mut().unwrap(),
+            })
+        } else {
+            Entry::Vacant(VacantEntry {
+                key,
+                slot,
+                length: &mut self.length,
+            })
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter {
+            stack: Vec::new(),
+            remaining: self.length,
+        };
+        iter.descend(self.root.as_deref());
+        iter
+    }
+
+    /// Removes `key` and returns its value. A node with two children is
+    /// replaced by the minimum of its right subtree.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let slot = Self::find_slot(&mut self.root, key);
+        let mut node = slot.take()?;
+
+        *slot = match (node.left.take(), node.right.take()) {
+            (None, None) => None,
+            (Some(child), None) | (None, Some(child)) => Some(child),
+            (Some(left), Some(right)) => {
+                let mut right = Some(right);
+                let mut successor = Self::take_min(&mut right);
+                successor.left = Some(left);
+                successor.right = right;
+                Some(successor)
+            }
+        };
+
+        self.length -= 1;
+        Some(node.value)
+    }
+
+    /// Walks down from `link` to the link that holds `key`, or to the empty
+    /// link where it would be inserted.
+    fn find_slot<'a>(mut link: &'a mut Link<K, V>, key: &K) -> &'a mut Link<K, V> {
+        loop {
+            let ordering = match link.as_deref() {
+                Some(node) => key.cmp(&node.key),
+                None => return link,
+            };
+            if ordering == Ordering::Equal {
+                return link;
+            }
+
+            let node = link.as_mut().unwrap();
+            link = if ordering == Ordering::Less {
+                &mut node.left
+            } else {
+                &mut node.right
+            };
+        }
+    }
+
+    /// Detaches the leftmost node under `link`, splicing its right child into
+    /// its place. `link` must not be empty.
+    fn take_min(mut link: &mut Link<K, V>) -> Box<Node<K, V>> {
+        while link.as_ref().is_some_and(|n| n.left.is_some()) {
+            link = &mut link.as_mut().unwrap().left;
+        }
+
+        let mut node = link.take().unwrap();
+        *link = node.right.take();
+        node
+    }
+}
+
+impl<K: Ord + Debug, V> TreeMap<K, V> {
+    /// Checks that keys are strictly ordered and that the length matches the
+    /// number of nodes.
+    pub fn validate(&self) -> Result<(), String> {
+        // an explicit stack, since a degenerate tree is as deep as it is long
+        let mut stack = Vec::new();
+        if let Some(root) = self.root.as_deref() {
+            stack.push((root, None::<&K>, None::<&K>));
+        }
+        let mut count = 0;
+
+        while let Some((node, lower, upper)) = stack.pop() {
+            count += 1;
+
+            if lower.is_some_and(|l| node.key <= *l) || upper.is_some_and(|u| node.key >= *u) {
+                return Err(format!("{:?} is out of order", node.key));
+            }
+
+            if let Some(ref left) = node.left {
+                stack.push((left, lower, Some(&node.key)));
+            }
+            if let Some(ref right) = node.right {
+                stack.push((right, Some(&node.key), upper));
+            }
+        }
+
+        if count != self.length {
+            return Err(format!(
+                "map holds {} nodes but length is {}",
+                count, self.length
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn descend(&mut self, mut link: Option<&'a Node<K, V>>) {
+        while let Some(node) = link {
+            self.stack.push(node);
+            link = node.left.as_deref();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.descend(node.right.as_deref());
+        self.remaining -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<'a, K: Ord, V> IntoIterator for &'a TreeMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V> Drop for TreeMap<K, V> {
+    /// Frees the nodes from an explicit stack so a map built from sorted keys
+    /// does not overflow the stack.
+    fn drop(&mut self) {
+        let mut stack: Vec<_> = self.root.take().into_iter().collect();
+
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
+impl<'a, K, V> Entry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(ref mut entry) = self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K, V: Default> Entry<'a, K, V> {
+    pub fn or_default(self) -> &'a mut V {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.node.key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.node.value
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.node.value
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.node.value
+    }
+
+    /// Replaces the value, returning the previous one.
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(&mut self.node.value, value)
+    }
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        *self.length += 1;
+        let node = self.slot.insert(Box::new(Node::new(self.key, value)));
+        &mut node.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::{Entry, TreeMap};
+
+    #[test]
+    fn insert_and_get_owned_values() {
+        let mut map = TreeMap::new();
+
+        assert_eq!(None, map.insert("banana".to_string(), vec![1]));
+        assert_eq!(None, map.insert("apple".to_string(), vec![2]));
+        assert_eq!(None, map.insert("cherry".to_string(), vec![3]));
+        assert_eq!(Some(vec![1]), map.insert("banana".to_string(), vec![4, 5]));
+
+        assert_eq!(3, map.len());
+        assert_eq!(Some(&vec![4, 5]), map.get(&"banana".to_string()));
+        assert_eq!(Some(&vec![2]), map.get(&"apple".to_string()));
+        assert_eq!(None, map.get(&"durian".to_string()));
+        assert!(map.contains_key(&"cherry".to_string()));
+    }
+
+    #[test]
+    fn get_mut_updates_in_place() {
+        let mut map = TreeMap::new();
+        map.insert(2, String::from("two"));
+        map.insert(1, String::from("one"));
+
+        map.get_mut(&2).unwrap().push('!');
+
+        assert_eq!(Some(&String::from("two!")), map.get(&2));
+        assert_eq!(None, map.get_mut(&3));
+    }
+
+    #[test]
+    fn remove_returns_old_value() {
+        let mut map = TreeMap::new();
+
+        for key in [10, 9, 11, 12, 18, 4, 3, 5] {
+            map.insert(key, key * 100);
+        }
+
+        assert_eq!(Some(1000), map.remove(&10));
+        assert_eq!(Some(400), map.remove(&4));
+        assert_eq!(None, map.remove(&4));
+        assert_eq!(6, map.len());
+
+        for key in [3, 5, 9, 11, 12, 18] {
+            assert_eq!(Some(&(key * 100)), map.get(&key));
+        }
+        assert_eq!(None, map.get(&10));
+
+        for key in [3, 5, 9, 11, 12, 18] {
+            assert_eq!(Some(key * 100), map.remove(&key));
+        }
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn entry_counts_words() {
+        let mut counts = TreeMap::new();
+
+        for word in "the quick fox jumps over the lazy dog the end".split(' ') {
+            *counts.entry(word).or_insert(0) += 1;
+        }
+
+        assert_eq!(8, counts.len());
+        assert_eq!(Some(&3), counts.get(&"the"));
+        assert_eq!(Some(&1), counts.get(&"fox"));
+    }
+
+    #[test]
+    fn entry_and_modify_or_default() {
+        let mut map: TreeMap<i32, Vec<i32>> = TreeMap::new();
+
+        map.entry(5).and_modify(|v| v.push(1)).or_default();
+        map.entry(5).and_modify(|v| v.push(2)).or_default();
+        map.entry(3).or_insert_with(|| vec![9]).push(10);
+
+        assert_eq!(Some(&vec![2]), map.get(&5));
+        assert_eq!(Some(&vec![9, 10]), map.get(&3));
+        assert_eq!(2, map.len());
+    }
+
+    #[test]
+    fn entry_variants() {
+        let mut map = TreeMap::new();
+        map.insert(1, "one");
+
+        match map.entry(1) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(&1, entry.key());
+                assert_eq!("one", entry.insert("uno"));
+                assert_eq!(&"uno", entry.get());
+            }
+            Entry::Vacant(_) => panic!("key 1 is present"),
+        }
+
+        match map.entry(2) {
+            Entry::Occupied(_) => panic!("key 2 is absent"),
+            Entry::Vacant(entry) => {
+                assert_eq!(&2, entry.key());
+                *entry.insert("two") = "dos";
+            }
+        }
+
+        assert_eq!(Some(&"uno"), map.get(&1));
+        assert_eq!(Some(&"dos"), map.get(&2));
+        assert_eq!(2, map.len());
+    }
+
+    /// Sorted keys leave a chain as long as the map. Insertion is quadratic on
+    /// it, so the chain is kept short and dropped on a small stack instead.
+    #[test]
+    fn drop_sorted_map() {
+        const COUNT: u32 = 20_000;
+
+        thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(|| {
+                let mut map = TreeMap::new();
+                for key in 0..COUNT {
+                    map.insert(key, key);
+                }
+
+                assert_eq!(COUNT as usize, map.len());
+                drop(map);
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+}
@@ -1,4 +1,20 @@
+pub mod arena;
+pub mod avl;
 pub mod binary;
+pub mod comparator;
+pub mod concurrent;
+pub mod interval;
+pub mod lending_iter_tree;
+pub mod map;
+pub mod multiset;
+pub mod persistent;
+pub mod red_black;
+pub mod render;
 pub mod tenary;
+pub mod treap;
 pub mod tries;
-pub mod lending_iter_tree;
\ No newline at end of file
+mod rng;
+#[cfg(test)]
+mod differential;
+#[cfg(test)]
+mod test_util;
@@ -0,0 +1,147 @@
+use std::fmt::Debug;
+
+use super::map::TreeMap;
+
+/// Ordered multiset: every distinct value sits in a single `TreeMap` node
+/// together with the number of times it was inserted.
+pub struct TreeMultiSet<V> {
+    counts: TreeMap<V, usize>,
+    length: usize,
+}
+
+impl<V: Ord> Default for TreeMultiSet<V> {
+    fn default() -> Self {
+        Self {
+            counts: TreeMap::new(),
+            length: 0,
+        }
+    }
+}
+
+impl<V: Ord> TreeMultiSet<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of values, counting every duplicate.
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Number of distinct values.
+    pub fn distinct_len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Adds one occurrence of `value` and returns its new count.
+    pub fn insert(&mut self, value: V) -> usize {
+        let count = self.counts.entry(value).or_insert(0);
+        *count += 1;
+        self.length += 1;
+        *count
+    }
+
+    pub fn count(&self, value: &V) -> usize {
+        self.counts.get(value).copied().unwrap_or(0)
+    }
+
+    pub fn contains(&self, value: &V) -> bool {
+        self.counts.contains_key(value)
+    }
+
+    /// Removes a single occurrence of `value`, dropping its node once the
+    /// count reaches zero. Returns `false` when the value is absent.
+    pub fn remove_one(&mut self, value: &V) -> bool {
+        match self.counts.get_mut(value) {
+            Some(count) if *count > 1 => *count -= 1,
+            Some(_) => {
+                self.counts.remove(value);
+            }
+            None => return false,
+        }
+
+        self.length -= 1;
+        true
+    }
+
+    /// Removes every occurrence of `value` and returns how many there were.
+    pub fn remove_all(&mut self, value: &V) -> usize {
+        let count = self.counts.remove(value).unwrap_or(0);
+        self.length -= count;
+        count
+    }
+}
+
+impl<V: Ord + Debug> TreeMultiSet<V> {
+    /// Checks the underlying map, that no value is kept with a zero count and
+    /// that the counts add up to the length.
+    pub fn validate(&self) -> Result<(), String> {
+        self.counts.validate()?;
+
+        let mut total = 0;
+        for (value, &count) in self.counts.iter() {
+            if count == 0 {
+                return Err(format!("{:?} is kept with a zero count", value));
+            }
+            total += count;
+        }
+
+        if total != self.length {
+            return Err(format!(
+                "counts add up to {} but length is {}",
+                total, self.length
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TreeMultiSet;
+
+    #[test]
+    fn insert_and_count_duplicates() {
+        let mut set = TreeMultiSet::new();
+
+        assert_eq!(1, set.insert(5));
+        assert_eq!(2, set.insert(5));
+        assert_eq!(1, set.insert(3));
+        assert_eq!(3, set.insert(5));
+
+        assert_eq!(4, set.len());
+        assert_eq!(2, set.distinct_len());
+        assert_eq!(3, set.count(&5));
+        assert_eq!(1, set.count(&3));
+        assert_eq!(0, set.count(&4));
+        assert!(set.contains(&3));
+    }
+
+    #[test]
+    fn remove_one_and_all() {
+        let mut set = TreeMultiSet::new();
+
+        for value in ["b", "a", "b", "c", "b", "a"] {
+            set.insert(value);
+        }
+
+        assert!(set.remove_one(&"b"));
+        assert_eq!(2, set.count(&"b"));
+        assert!(set.remove_one(&"c"));
+        assert!(!set.contains(&"c"));
+        assert!(!set.remove_one(&"c"));
+
+        assert_eq!(2, set.remove_all(&"a"));
+        assert_eq!(0, set.remove_all(&"a"));
+        assert_eq!(2, set.len());
+        assert_eq!(1, set.distinct_len());
+
+        assert_eq!(2, set.remove_all(&"b"));
+        assert!(set.is_empty());
+    }
+}
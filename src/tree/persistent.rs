@@ -0,0 +1,473 @@
+use std::{cmp::Ordering, fmt::Debug, marker::PhantomData, rc::Rc};
+
+use super::comparator::{Comparator, Natural};
+
+pub type Link<V> = Option<Rc<Node<V>>>;
+
+/// Immutable node. Children are shared between every version that has not
+/// rebuilt them, so a node has no parent pointer.
+#[derive(Debug)]
+pub struct Node<V> {
+    pub value: V,
+    pub height: usize,
+
+    pub left: Link<V>,
+    pub right: Link<V>,
+}
+
+/// Persistent AVL tree. `insert` and `remove` leave `self` untouched and
+/// return a new version that copies only the nodes on the search path, so
+/// both cost `O(log n)` time and allocations while every older version stays
+/// valid. Cloning a version is a single refcount bump.
+pub struct PersistentTree<V, C = Natural> {
+    root: Link<V>,
+    length: usize,
+    comparator: C,
+}
+
+/// In-order iterator over one version.
+pub struct Iter<'a, V> {
+    stack: Vec<Rc<Node<V>>>,
+    _tree: PhantomData<&'a ()>,
+}
+
+fn height<V>(link: &Link<V>) -> usize {
+    link.as_ref().map_or(0, |n| n.height)
+}
+
+fn make<V>(value: V, left: Link<V>, right: Link<V>) -> Rc<Node<V>> {
+    Rc::new(Node {
+        value,
+        height: 1 + height(&left).max(height(&right)),
+        left,
+        right,
+    })
+}
+
+/// Builds a node from subtrees whose heights differ by at most two, rotating
+/// once or twice to bring the difference back within one. Only the nodes that
+/// move get rebuilt; everything below them is shared.
+fn balance<V: Copy>(value: V, left: Link<V>, right: Link<V>) -> Rc<Node<V>> {
+    let (left_height, right_height) = (height(&left), height(&right));
+
+    if left_height > right_height + 1 {
+        let l = left.unwrap();
+        if height(&l.left) >= height(&l.right) {
+            let right = make(value, l.right.clone(), right);
+            make(l.value, l.left.clone(), Some(right))
+        } else {
+            let lr = l.right.as_ref().unwrap();
+            let left = make(l.value, l.left.clone(), lr.left.clone());
+            let right = make(value, lr.right.clone(), right);
+            make(lr.value, Some(left), Some(right))
+        }
+    } else if right_height > left_height + 1 {
+        let r = right.unwrap();
+        if height(&r.right) >= height(&r.left) {
+            let left = make(value, left, r.left.clone());
+            make(r.value, Some(left), r.right.clone())
+        } else {
+            let rl = r.left.as_ref().unwrap();
+            let left = make(value, left, rl.left.clone());
+            let right = make(r.value, rl.right.clone(), r.right.clone());
+            make(rl.value, Some(left), Some(right))
+        }
+    } else {
+        make(value, left, right)
+    }
+}
+
+/// Returns the rebuilt subtree, or `None` when `value` is already present and
+/// nothing had to be copied.
+fn insert<V: Copy, C: Comparator<V>>(link: &Link<V>, value: V, comparator: &C) -> Option<Link<V>> {
+    let node = match link {
+        Some(node) => node,
+        None => return Some(Some(make(value, None, None))),
+    };
+
+    let rebuilt = match comparator.compare(&value, &node.value) {
+        Ordering::Equal => return None,
+        Ordering::Less => {
+            let left = insert(&node.left, value, comparator)?;
+            balance(node.value, left, node.right.clone())
+        }
+        Ordering::Greater => {
+            let right = insert(&node.right, value, comparator)?;
+            balance(node.value, node.left.clone(), right)
+        }
+    };
+
+    Some(Some(rebuilt))
+}
+
+/// Returns the rebuilt subtree, or `None` when `value` is absent.
+fn remove<V: Copy, C: Comparator<V>>(link: &Link<V>, value: &V, comparator: &C) -> Option<Link<V>> {
+    let node = link.as_ref()?;
+
+    let rebuilt = match comparator.compare(value, &node.value) {
+        Ordering::Less => {
+            let left = remove(&node.left, value, comparator)?;
+            balance(node.value, left, node.right.clone())
+        }
+        Ordering::Greater => {
+            let right = remove(&node.right, value, comparator)?;
+            balance(node.value, node.left.clone(), right)
+        }
+        Ordering::Equal => match (&node.left, &node.right) {
+            (None, child) | (child, None) => return Some(child.clone()),
+            (left, Some(right)) => {
+                let (successor, right) = remove_min(right);
+                balance(successor, left.clone(), right)
+            }
+        },
+    };
+
+    Some(Some(rebuilt))
+}
+
+/// Splits off the smallest value of a non-empty subtree.
+fn remove_min<V: Copy>(node: &Rc<Node<V>>) -> (V, Link<V>) {
+    match node.left {
+        None => (node.value, node.right.clone()),
+        Some(ref left) => {
+            let (min, left) = remove_min(left);
+            (min, Some(balance(node.value, left, node.right.clone())))
+        }
+    }
+}
+
+impl<V, C: Clone> Clone for PersistentTree<V, C> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            length: self.length,
+            comparator: self.comparator.clone(),
+        }
+    }
+}
+
+impl<V, C: Default> Default for PersistentTree<V, C> {
+    fn default() -> Self {
+        Self::with_comparator(C::default())
+    }
+}
+
+impl<V: PartialOrd + Copy + Debug> PersistentTree<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<V, C> PersistentTree<V, C> {
+    /// Empty tree ordered by `comparator` instead of the natural order.
+    pub fn with_comparator(comparator: C) -> Self {
+        Self {
+            root: None,
+            length: 0,
+            comparator,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn height(&self) -> usize {
+        height(&self.root)
+    }
+
+    /// Whether both versions share the same root, which is cheaper than
+    /// comparing their contents.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.root, &other.root) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V> + Clone> PersistentTree<V, C> {
+    /// Returns a version that also holds `value`. When it is already present
+    /// the result shares its root with `self`.
+    pub fn insert(&self, value: V) -> Self {
+        match insert(&self.root, value, &self.comparator) {
+            Some(root) => Self {
+                root,
+                length: self.length + 1,
+                comparator: self.comparator.clone(),
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// Returns a version without `value`. When it is absent the result
+    /// shares its root with `self`.
+    pub fn remove(&self, value: &V) -> Self {
+        match remove(&self.root, value, &self.comparator) {
+            Some(root) => Self {
+                root,
+                length: self.length - 1,
+                comparator: self.comparator.clone(),
+            },
+            None => self.clone(),
+        }
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V>> PersistentTree<V, C> {
+    /// Checks that values are ordered, stored heights are accurate, no
+    /// balance factor leaves `-1..=1` and the length matches the node count.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut count = 0;
+        self.validate_node(&self.root, None, None, &mut count)?;
+
+        if count != self.length {
+            return Err(format!(
+                "tree holds {} nodes but length is {}",
+                count, self.length
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the real height of `link`.
+    fn validate_node(
+        &self,
+        link: &Link<V>,
+        lower: Option<V>,
+        upper: Option<V>,
+        count: &mut usize,
+    ) -> Result<usize, String> {
+        let Some(node) = link else {
+            return Ok(0);
+        };
+        *count += 1;
+
+        let below = lower.is_some_and(|l| self.comparator.compare(&node.value, &l).is_le());
+        let above = upper.is_some_and(|u| self.comparator.compare(&node.value, &u).is_ge());
+        if below || above {
+            return Err(format!("{:?} is out of order", node.value));
+        }
+
+        let left = self.validate_node(&node.left, lower, Some(node.value), count)?;
+        let right = self.validate_node(&node.right, Some(node.value), upper, count)?;
+
+        if left.abs_diff(right) > 1 {
+            return Err(format!(
+                "{:?} is out of balance: {} vs {}",
+                node.value, left, right
+            ));
+        }
+        if node.height != 1 + left.max(right) {
+            return Err(format!(
+                "{:?} stores height {} but is {} high",
+                node.value,
+                node.height,
+                1 + left.max(right)
+            ));
+        }
+
+        Ok(node.height)
+    }
+
+    pub fn contains(&self, value: &V) -> bool {
+        let mut cursor = self.root.as_ref();
+
+        while let Some(node) = cursor {
+            cursor = match self.comparator.compare(value, &node.value) {
+                Ordering::Equal => return true,
+                Ordering::Less => node.left.as_ref(),
+                Ordering::Greater => node.right.as_ref(),
+            };
+        }
+
+        false
+    }
+
+    pub fn min(&self) -> Option<V> {
+        let mut node = self.root.as_ref()?;
+        while let Some(ref left) = node.left {
+            node = left;
+        }
+        Some(node.value)
+    }
+
+    pub fn max(&self) -> Option<V> {
+        let mut node = self.root.as_ref()?;
+        while let Some(ref right) = node.right {
+            node = right;
+        }
+        Some(node.value)
+    }
+
+    pub fn iter(&self) -> Iter<'_, V> {
+        let mut iter = Iter {
+            stack: Vec::new(),
+            _tree: PhantomData,
+        };
+        iter.descend(self.root.clone());
+        iter
+    }
+}
+
+impl<V: Copy + Debug, C: Comparator<V> + Default> FromIterator<V> for PersistentTree<V, C> {
+    fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
+        let comparator = C::default();
+        let mut root = None;
+        let mut length = 0;
+
+        for value in iter {
+            if let Some(new_root) = insert(&root, value, &comparator) {
+                root = new_root;
+                length += 1;
+            }
+        }
+
+        Self {
+            root,
+            length,
+            comparator,
+        }
+    }
+}
+
+impl<V> Iter<'_, V> {
+    fn descend(&mut self, mut link: Link<V>) {
+        while let Some(node) = link {
+            link = node.left.clone();
+            self.stack.push(node);
+        }
+    }
+}
+
+impl<V: Copy> Iterator for Iter<'_, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.descend(node.right.clone());
+        Some(node.value)
+    }
+}
+
+impl<'a, V: Copy + Debug, C: Comparator<V>> IntoIterator for &'a PersistentTree<V, C> {
+    type Item = V;
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeSet, HashSet},
+        rc::Rc,
+    };
+
+    use super::{Link, Node, PersistentTree};
+    use crate::tree::test_util::XorShift;
+
+    /// Returns the real height of `link` while asserting the AVL balance and
+    /// that stored heights are accurate.
+    fn check_node(link: &Link<i32>) -> usize {
+        let Some(node) = link else {
+            return 0;
+        };
+
+        let left = check_node(&node.left);
+        let right = check_node(&node.right);
+        assert!(left.abs_diff(right) <= 1);
+        assert_eq!(1 + left.max(right), node.height);
+        node.height
+    }
+
+    fn collect_nodes(link: &Link<i32>, nodes: &mut HashSet<*const Node<i32>>) {
+        if let Some(node) = link {
+            nodes.insert(Rc::as_ptr(node));
+            collect_nodes(&node.left, nodes);
+            collect_nodes(&node.right, nodes);
+        }
+    }
+
+    #[test]
+    fn old_versions_stay_valid() {
+        let empty = PersistentTree::new();
+        let one = empty.insert(10);
+        let two = one.insert(5);
+        let three = two.insert(15);
+        let without_ten = three.remove(&10);
+
+        assert!(empty.is_empty());
+        assert_eq!(vec![10], one.iter().collect::<Vec<_>>());
+        assert_eq!(vec![5, 10], two.iter().collect::<Vec<_>>());
+        assert_eq!(vec![5, 10, 15], three.iter().collect::<Vec<_>>());
+        assert_eq!(vec![5, 15], without_ten.iter().collect::<Vec<_>>());
+
+        assert!(three.contains(&10));
+        assert!(!without_ten.contains(&10));
+        assert_eq!(Some(5), three.min());
+        assert_eq!(Some(15), three.max());
+    }
+
+    #[test]
+    fn unchanged_versions_share_their_root() {
+        let tree: PersistentTree<i32> = (0..100).collect();
+
+        assert!(tree.insert(42).ptr_eq(&tree));
+        assert!(tree.remove(&1000).ptr_eq(&tree));
+        assert!(tree.clone().ptr_eq(&tree));
+        assert!(!tree.insert(1000).ptr_eq(&tree));
+    }
+
+    #[test]
+    fn updates_copy_only_the_search_path() {
+        let tree: PersistentTree<i32> = (0..1024).map(|v| v * 2).collect();
+        let mut old = HashSet::new();
+        collect_nodes(&tree.root, &mut old);
+
+        for version in [tree.insert(777), tree.remove(&1000), tree.remove(&0)] {
+            let mut new = HashSet::new();
+            collect_nodes(&version.root, &mut new);
+
+            // a rotation may rebuild one extra node per level on the way up
+            let copied = new.difference(&old).count();
+            assert!(copied <= 2 * tree.height(), "copied {copied} nodes");
+        }
+    }
+
+    #[test]
+    fn random_versions_match_btreeset() {
+        let mut rng = XorShift::new(0x9E37_79B9_7F4A_7C15);
+        let mut versions = vec![(PersistentTree::new(), BTreeSet::new())];
+
+        for _ in 0..2000 {
+            let (tree, expected) = &versions[(rng.next_u64() % versions.len() as u64) as usize];
+            let (mut tree, mut expected) = (tree.clone(), expected.clone());
+
+            let value = (rng.next_u64() % 256) as i32;
+            if rng.next_u64().is_multiple_of(3) {
+                tree = tree.remove(&value);
+                expected.remove(&value);
+            } else {
+                tree = tree.insert(value);
+                expected.insert(value);
+            }
+
+            check_node(&tree.root);
+            versions.push((tree, expected));
+        }
+
+        for (tree, expected) in &versions {
+            assert_eq!(expected.len(), tree.len());
+            assert!(tree.iter().eq(expected.iter().copied()));
+        }
+    }
+}
@@ -0,0 +1,653 @@
+use std::{
+    cell::RefCell,
+    fmt::Debug,
+    marker::PhantomData,
+    rc::{Rc, Weak},
+};
+
+use super::binary::{BackLink, Link};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Black,
+}
+
+#[derive(Debug, Clone)]
+pub struct Node<V> {
+    pub value: V,
+    pub color: Color,
+
+    pub parent: BackLink<Node<V>>,
+    pub left: Link<Node<V>>,
+    pub right: Link<Node<V>>,
+}
+
+/// Red-black tree using the same `Rc`/`Weak` parent-pointer layout as
+/// `BinaryTree`. Rebalancing needs at most two rotations per insert and three
+/// per delete, trading a slightly deeper tree than AVL for cheaper writes.
+pub struct RedBlackTree<V> {
+    root: Link<Node<V>>,
+    length: usize,
+}
+
+/// In-order iterator that follows parent links instead of keeping a stack.
+pub struct Iter<'a, V> {
+    next: Link<Node<V>>,
+    remaining: usize,
+    _tree: PhantomData<&'a RedBlackTree<V>>,
+}
+
+impl<V> From<Node<V>> for Link<Node<V>> {
+    fn from(value: Node<V>) -> Self {
+        Some(Rc::new(RefCell::new(value)))
+    }
+}
+
+impl<V> Node<V> {
+    fn new(value: V, color: Color) -> Self {
+        Self {
+            value,
+            color,
+            parent: None,
+            left: None,
+            right: None,
+        }
+    }
+
+    fn with_parent(mut self, parent: &Rc<RefCell<Node<V>>>) -> Self {
+        self.parent = Some(Rc::downgrade(parent));
+        self
+    }
+}
+
+fn is_red<V>(node: &Link<Node<V>>) -> bool {
+    node.as_ref()
+        .is_some_and(|n| n.borrow().color == Color::Red)
+}
+
+fn set_color<V>(node: &Link<Node<V>>, color: Color) {
+    if let Some(n) = node {
+        n.borrow_mut().color = color;
+    }
+}
+
+fn parent_of<V>(node: &Rc<RefCell<Node<V>>>) -> Link<Node<V>> {
+    node.borrow().parent.as_ref().and_then(Weak::upgrade)
+}
+
+fn is_left_child<V>(parent: &Rc<RefCell<Node<V>>>, node: &Rc<RefCell<Node<V>>>) -> bool {
+    parent
+        .borrow()
+        .left
+        .as_ref()
+        .is_some_and(|l| Rc::ptr_eq(l, node))
+}
+
+fn successor<V>(node: &Rc<RefCell<Node<V>>>) -> Link<Node<V>> {
+    let right = node.borrow().right.clone();
+    if let Some(mut cursor) = right {
+        loop {
+            let left = cursor.borrow().left.clone();
+            match left {
+                Some(l) => cursor = l,
+                None => return Some(cursor),
+            }
+        }
+    }
+
+    let mut child = node.clone();
+    while let Some(parent) = parent_of(&child) {
+        if is_left_child(&parent, &child) {
+            return Some(parent);
+        }
+        child = parent;
+    }
+
+    None
+}
+
+impl<V: PartialEq + PartialOrd + Copy + Debug> RedBlackTree<V> {
+    pub fn new(value: V) -> Self {
+        Self {
+            root: Node::new(value, Color::Black).into(),
+            length: 1,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Inserts `value` as a red leaf and repairs any red-red violation on the
+    /// way up. Returns `false` when the value is already present.
+    pub fn insert(&mut self, value: V) -> bool {
+        let mut cursor = match self.root.clone() {
+            Some(root) => root,
+            None => {
+                self.root = Node::new(value, Color::Black).into();
+                self.length += 1;
+                return true;
+            }
+        };
+
+        loop {
+            let next = if value < cursor.borrow().value {
+                cursor.borrow().left.clone()
+            } else if value > cursor.borrow().value {
+                cursor.borrow().right.clone()
+            } else {
+                return false;
+            };
+
+            match next {
+                Some(node) => cursor = node,
+                None => break,
+            }
+        }
+
+        let node: Rc<RefCell<Node<V>>> = Rc::new(RefCell::new(
+            Node::new(value, Color::Red).with_parent(&cursor),
+        ));
+        if value < cursor.borrow().value {
+            cursor.borrow_mut().left = Some(node.clone());
+        } else {
+            cursor.borrow_mut().right = Some(node.clone());
+        }
+
+        self.length += 1;
+        self.insert_fixup(node);
+        true
+    }
+
+    /// Removes `value`. A node with two children takes its in-order
+    /// successor's value and the successor is spliced out instead; removing a
+    /// black node triggers the double-black fixup.
+    pub fn delete(&mut self, value: V) -> bool {
+        let target = match self.find(value) {
+            Some(target) => target,
+            None => return false,
+        };
+
+        let has_both = {
+            let t = target.borrow();
+            t.left.is_some() && t.right.is_some()
+        };
+
+        let removed = if has_both {
+            let next = successor(&target).unwrap();
+            let next_value = next.borrow().value;
+            target.borrow_mut().value = next_value;
+            next
+        } else {
+            target
+        };
+
+        let (parent, child, color) = {
+            let mut r = removed.borrow_mut();
+            let child = r.left.take().or_else(|| r.right.take());
+            (r.parent.take(), child, r.color)
+        };
+
+        if let Some(ref c) = child {
+            c.borrow_mut().parent = parent.clone();
+        }
+        self.replace_child(&parent, &removed, child.clone());
+        self.length -= 1;
+
+        if color == Color::Black {
+            self.delete_fixup(child, parent.and_then(|p| p.upgrade()));
+        }
+        true
+    }
+
+    pub fn min(&self) -> Option<V> {
+        let mut cursor = self.root.clone()?;
+
+        loop {
+            let left = cursor.borrow().left.clone();
+            match left {
+                Some(l) => cursor = l,
+                None => break,
+            }
+        }
+
+        let value = cursor.borrow().value;
+        Some(value)
+    }
+
+    pub fn max(&self) -> Option<V> {
+        let mut cursor = self.root.clone()?;
+
+        loop {
+            let right = cursor.borrow().right.clone();
+            match right {
+                Some(r) => cursor = r,
+                None => break,
+            }
+        }
+
+        let value = cursor.borrow().value;
+        Some(value)
+    }
+
+    pub fn find(&self, value: V) -> Link<Node<V>> {
+        let mut cursor = self.root.clone();
+
+        while let Some(node) = cursor {
+            if node.borrow().value == value {
+                return Some(node);
+            }
+
+            cursor = if value < node.borrow().value {
+                node.borrow().left.clone()
+            } else {
+                node.borrow().right.clone()
+            };
+        }
+
+        None
+    }
+
+    pub fn search(&self, value: V) -> bool {
+        self.find(value).is_some()
+    }
+
+    pub fn iter(&self) -> Iter<'_, V> {
+        let mut first = self.root.clone();
+
+        if let Some(mut cursor) = first.take() {
+            loop {
+                let left = cursor.borrow().left.clone();
+                match left {
+                    Some(l) => cursor = l,
+                    None => break,
+                }
+            }
+            first = Some(cursor);
+        }
+
+        Iter {
+            next: first,
+            remaining: self.length,
+            _tree: PhantomData,
+        }
+    }
+
+    /// Checks every red-black invariant and returns the black height of the
+    /// tree: the root is black, no red node has a red child, every root-to-leaf
+    /// path crosses the same number of black nodes, values are ordered and
+    /// each child links back to its parent.
+    pub fn validate(&self) -> Result<usize, String> {
+        let root = match self.root {
+            Some(ref root) => root,
+            None if self.length == 0 => return Ok(0),
+            None => return Err(format!("empty tree reports length {}", self.length)),
+        };
+
+        if root.borrow().color != Color::Black {
+            return Err("root is red".to_string());
+        }
+        if root.borrow().parent.is_some() {
+            return Err("root has a parent".to_string());
+        }
+
+        let mut count = 0;
+        let black_height = Self::validate_node(root, None, None, &mut count)?;
+        if count != self.length {
+            return Err(format!(
+                "tree holds {} nodes but length is {}",
+                count, self.length
+            ));
+        }
+
+        Ok(black_height)
+    }
+
+    fn validate_node(
+        node: &Rc<RefCell<Node<V>>>,
+        lower: Option<V>,
+        upper: Option<V>,
+        count: &mut usize,
+    ) -> Result<usize, String> {
+        let n = node.borrow();
+        *count += 1;
+
+        if lower.is_some_and(|l| n.value <= l) || upper.is_some_and(|u| n.value >= u) {
+            return Err(format!("{:?} is out of order", n.value));
+        }
+
+        let mut heights = [0, 0];
+        for (i, child) in [&n.left, &n.right].into_iter().enumerate() {
+            let Some(c) = child else {
+                continue;
+            };
+
+            let linked = c.borrow().parent.as_ref().and_then(Weak::upgrade);
+            if !linked.is_some_and(|p| Rc::ptr_eq(&p, node)) {
+                return Err(format!("child of {:?} does not link back to it", n.value));
+            }
+            if n.color == Color::Red && c.borrow().color == Color::Red {
+                return Err(format!("red node {:?} has a red child", n.value));
+            }
+
+            heights[i] = if i == 0 {
+                Self::validate_node(c, lower, Some(n.value), count)?
+            } else {
+                Self::validate_node(c, Some(n.value), upper, count)?
+            };
+        }
+
+        if heights[0] != heights[1] {
+            return Err(format!(
+                "black height differs under {:?}: {} vs {}",
+                n.value, heights[0], heights[1]
+            ));
+        }
+
+        Ok(heights[0] + usize::from(n.color == Color::Black))
+    }
+
+    fn insert_fixup(&mut self, mut node: Rc<RefCell<Node<V>>>) {
+        while let Some(parent) = parent_of(&node).filter(|p| p.borrow().color == Color::Red) {
+            // a red parent is never the root, so the grandparent exists
+            let grand = parent_of(&parent).unwrap();
+
+            if is_left_child(&grand, &parent) {
+                let uncle = grand.borrow().right.clone();
+                if is_red(&uncle) {
+                    parent.borrow_mut().color = Color::Black;
+                    set_color(&uncle, Color::Black);
+                    grand.borrow_mut().color = Color::Red;
+                    node = grand;
+                    continue;
+                }
+
+                let parent = if !is_left_child(&parent, &node) {
+                    self.rotate_left(parent.clone());
+                    node = parent;
+                    parent_of(&node).unwrap()
+                } else {
+                    parent
+                };
+                parent.borrow_mut().color = Color::Black;
+                grand.borrow_mut().color = Color::Red;
+                self.rotate_right(grand);
+            } else {
+                let uncle = grand.borrow().left.clone();
+                if is_red(&uncle) {
+                    parent.borrow_mut().color = Color::Black;
+                    set_color(&uncle, Color::Black);
+                    grand.borrow_mut().color = Color::Red;
+                    node = grand;
+                    continue;
+                }
+
+                let parent = if is_left_child(&parent, &node) {
+                    self.rotate_right(parent.clone());
+                    node = parent;
+                    parent_of(&node).unwrap()
+                } else {
+                    parent
+                };
+                parent.borrow_mut().color = Color::Black;
+                grand.borrow_mut().color = Color::Red;
+                self.rotate_left(grand);
+            }
+        }
+
+        set_color(&self.root, Color::Black);
+    }
+
+    /// `node` carries an extra black after a black node was spliced out below
+    /// `parent`. It may be `None`, which is why the parent is tracked
+    /// separately.
+    fn delete_fixup(&mut self, mut node: Link<Node<V>>, mut parent: Link<Node<V>>) {
+        while !is_red(&node) {
+            let Some(p) = parent.clone() else {
+                break;
+            };
+
+            let node_is_left = match node {
+                Some(ref n) => is_left_child(&p, n),
+                None => p.borrow().left.is_none(),
+            };
+
+            if node_is_left {
+                let mut sibling = p.borrow().right.clone().unwrap();
+                if sibling.borrow().color == Color::Red {
+                    sibling.borrow_mut().color = Color::Black;
+                    p.borrow_mut().color = Color::Red;
+                    self.rotate_left(p.clone());
+                    sibling = p.borrow().right.clone().unwrap();
+                }
+
+                let (near, far) = {
+                    let s = sibling.borrow();
+                    (s.left.clone(), s.right.clone())
+                };
+                if !is_red(&near) && !is_red(&far) {
+                    sibling.borrow_mut().color = Color::Red;
+                    parent = parent_of(&p);
+                    node = Some(p);
+                    continue;
+                }
+
+                if !is_red(&far) {
+                    set_color(&near, Color::Black);
+                    sibling.borrow_mut().color = Color::Red;
+                    self.rotate_right(sibling);
+                    sibling = p.borrow().right.clone().unwrap();
+                }
+
+                let parent_color = p.borrow().color;
+                sibling.borrow_mut().color = parent_color;
+                p.borrow_mut().color = Color::Black;
+                set_color(&sibling.borrow().right, Color::Black);
+                self.rotate_left(p);
+            } else {
+                let mut sibling = p.borrow().left.clone().unwrap();
+                if sibling.borrow().color == Color::Red {
+                    sibling.borrow_mut().color = Color::Black;
+                    p.borrow_mut().color = Color::Red;
+                    self.rotate_right(p.clone());
+                    sibling = p.borrow().left.clone().unwrap();
+                }
+
+                let (near, far) = {
+                    let s = sibling.borrow();
+                    (s.right.clone(), s.left.clone())
+                };
+                if !is_red(&near) && !is_red(&far) {
+                    sibling.borrow_mut().color = Color::Red;
+                    parent = parent_of(&p);
+                    node = Some(p);
+                    continue;
+                }
+
+                if !is_red(&far) {
+                    set_color(&near, Color::Black);
+                    sibling.borrow_mut().color = Color::Red;
+                    self.rotate_left(sibling);
+                    sibling = p.borrow().left.clone().unwrap();
+                }
+
+                let parent_color = p.borrow().color;
+                sibling.borrow_mut().color = parent_color;
+                p.borrow_mut().color = Color::Black;
+                set_color(&sibling.borrow().left, Color::Black);
+                self.rotate_right(p);
+            }
+
+            node = self.root.clone();
+            break;
+        }
+
+        set_color(&node, Color::Black);
+    }
+
+    fn rotate_left(&mut self, node: Rc<RefCell<Node<V>>>) {
+        let pivot = node.borrow_mut().right.take().unwrap();
+        let inner = pivot.borrow_mut().left.take();
+
+        if let Some(ref i) = inner {
+            i.borrow_mut().parent = Some(Rc::downgrade(&node));
+        }
+        node.borrow_mut().right = inner;
+
+        let parent = node.borrow_mut().parent.take();
+        pivot.borrow_mut().parent = parent.clone();
+        self.replace_child(&parent, &node, Some(pivot.clone()));
+
+        node.borrow_mut().parent = Some(Rc::downgrade(&pivot));
+        pivot.borrow_mut().left = Some(node);
+    }
+
+    fn rotate_right(&mut self, node: Rc<RefCell<Node<V>>>) {
+        let pivot = node.borrow_mut().left.take().unwrap();
+        let inner = pivot.borrow_mut().right.take();
+
+        if let Some(ref i) = inner {
+            i.borrow_mut().parent = Some(Rc::downgrade(&node));
+        }
+        node.borrow_mut().left = inner;
+
+        let parent = node.borrow_mut().parent.take();
+        pivot.borrow_mut().parent = parent.clone();
+        self.replace_child(&parent, &node, Some(pivot.clone()));
+
+        node.borrow_mut().parent = Some(Rc::downgrade(&pivot));
+        pivot.borrow_mut().right = Some(node);
+    }
+
+    fn replace_child(
+        &mut self,
+        parent: &BackLink<Node<V>>,
+        old: &Rc<RefCell<Node<V>>>,
+        new: Link<Node<V>>,
+    ) {
+        match parent.as_ref().and_then(Weak::upgrade) {
+            Some(p) => {
+                if is_left_child(&p, old) {
+                    p.borrow_mut().left = new;
+                } else {
+                    p.borrow_mut().right = new;
+                }
+            }
+            None => self.root = new,
+        }
+    }
+}
+
+impl<V: Copy> Iterator for Iter<'_, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+        self.next = successor(&node);
+        self.remaining -= 1;
+
+        let value = node.borrow().value;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, V: PartialEq + PartialOrd + Copy + Debug> IntoIterator for &'a RedBlackTree<V> {
+    type Item = V;
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::RedBlackTree;
+    use crate::tree::test_util::XorShift;
+
+    #[test]
+    fn insert_and_iterate_in_order() {
+        let mut tree = RedBlackTree::new(10);
+
+        for value in [9, 11, 12, 18, 4, 3, 5] {
+            assert!(tree.insert(value));
+        }
+        assert!(!tree.insert(12));
+
+        assert_eq!(Ok(2), tree.validate());
+        assert_eq!(
+            vec![3, 4, 5, 9, 10, 11, 12, 18],
+            tree.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(Some(3), tree.min());
+        assert_eq!(Some(18), tree.max());
+        assert!(tree.search(9));
+        assert!(!tree.search(8));
+    }
+
+    #[test]
+    fn sorted_inserts_stay_shallow() {
+        let mut tree = RedBlackTree::new(0);
+
+        for i in 1..1024 {
+            tree.insert(i);
+        }
+
+        let black_height = tree.validate().unwrap();
+        assert!(black_height <= 11);
+        assert_eq!(1024, tree.iter().count());
+    }
+
+    #[test]
+    fn delete_until_empty() {
+        let mut tree = RedBlackTree::new(1);
+        tree.insert(2);
+        tree.insert(3);
+
+        assert!(tree.delete(2));
+        assert!(!tree.delete(2));
+        assert!(tree.delete(1));
+        assert!(tree.delete(3));
+
+        assert!(tree.is_empty());
+        assert_eq!(Ok(0), tree.validate());
+        assert_eq!(None, tree.min());
+
+        tree.insert(7);
+        assert_eq!(vec![7], tree.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn random_operations_keep_invariants() {
+        let mut rng = XorShift::new(0x2545_F491_4F6C_DD1D);
+        let mut tree = RedBlackTree::new(0);
+        let mut expected = BTreeSet::from([0]);
+
+        for _ in 0..5000 {
+            let value = (rng.next_u64() % 512) as i32;
+            if rng.next_u64().is_multiple_of(3) {
+                assert_eq!(expected.remove(&value), tree.delete(value));
+            } else {
+                assert_eq!(expected.insert(value), tree.insert(value));
+            }
+            tree.validate().unwrap();
+        }
+
+        assert_eq!(expected.len(), tree.len());
+        assert_eq!(
+            expected.into_iter().collect::<Vec<_>>(),
+            tree.iter().collect::<Vec<_>>()
+        );
+    }
+}
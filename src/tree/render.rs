@@ -0,0 +1,292 @@
+//! Text renderings of trees for debugging: Graphviz DOT and an indented
+//! box-drawing view. Each tree lowers itself into a `Layout`, and both
+//! formats are produced from that.
+
+use std::fmt::{Display, Write};
+
+/// What to draw beyond the values and the child edges.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderOptions {
+    /// Draw each node's parent pointer, for trees that keep one.
+    pub parent_links: bool,
+    /// Annotate every node with its height and whatever the tree stores per
+    /// node.
+    pub metadata: bool,
+}
+
+/// A tree flattened for rendering. Nodes are numbered in preorder, so a
+/// child always has a larger id than its parent.
+#[derive(Debug, Default)]
+pub struct Layout {
+    nodes: Vec<LayoutNode>,
+    roots: Vec<usize>,
+}
+
+#[derive(Debug)]
+struct LayoutNode {
+    label: String,
+    metadata: Vec<(&'static str, String)>,
+    /// Child ids with the label of the edge leading to them.
+    children: Vec<(&'static str, usize)>,
+    parent: Option<ParentLink>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ParentLink {
+    Node(usize),
+    /// The pointer is set but leads outside the rendered tree.
+    Dangling,
+}
+
+/// Trees that can be drawn. Implementors only describe their shape; the
+/// output formats are shared.
+pub trait Render {
+    fn layout(&self) -> Layout;
+
+    fn to_dot(&self, options: &RenderOptions) -> String {
+        self.layout().to_dot(options)
+    }
+
+    fn to_ascii(&self, options: &RenderOptions) -> String {
+        self.layout().to_ascii(options)
+    }
+}
+
+impl Layout {
+    /// Adds a node below `parent`, reached over an edge with the given label,
+    /// or as a new root when `parent` is `None`. Returns the new node's id.
+    pub fn add(&mut self, parent: Option<(usize, &'static str)>, label: String) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(LayoutNode {
+            label,
+            metadata: Vec::new(),
+            children: Vec::new(),
+            parent: None,
+        });
+
+        match parent {
+            Some((parent, edge)) => self.nodes[parent].children.push((edge, id)),
+            None => self.roots.push(id),
+        }
+        id
+    }
+
+    pub fn annotate(&mut self, id: usize, key: &'static str, value: impl Display) {
+        self.nodes[id].metadata.push((key, value.to_string()));
+    }
+
+    /// Records where the node's parent pointer leads; `None` marks a pointer
+    /// that is set but does not lead to a node of this layout.
+    pub fn link_parent(&mut self, id: usize, parent: Option<usize>) {
+        self.nodes[id].parent = Some(parent.map_or(ParentLink::Dangling, ParentLink::Node));
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Height of every node, a leaf being 1. Children come after their
+    /// parents, so one backwards pass sees every child first.
+    fn heights(&self) -> Vec<usize> {
+        let mut heights = vec![1; self.nodes.len()];
+        for id in (0..self.nodes.len()).rev() {
+            let below = self.nodes[id]
+                .children
+                .iter()
+                .map(|&(_, child)| heights[child])
+                .max();
+            heights[id] += below.unwrap_or(0);
+        }
+        heights
+    }
+
+    fn metadata(&self, id: usize, heights: &[usize]) -> String {
+        let mut text = String::new();
+        for (key, value) in &self.nodes[id].metadata {
+            write!(text, "{key}={value} ").unwrap();
+        }
+        write!(text, "height={}", heights[id]).unwrap();
+        text
+    }
+
+    pub fn to_dot(&self, options: &RenderOptions) -> String {
+        let heights = self.heights();
+        let mut out = String::from("digraph tree {\n    node [shape=box];\n");
+
+        for (id, node) in self.nodes.iter().enumerate() {
+            let mut label = escape(&node.label);
+            if options.metadata {
+                write!(label, "\\n{}", escape(&self.metadata(id, &heights))).unwrap();
+            }
+            writeln!(out, "    n{id} [label=\"{label}\"];").unwrap();
+        }
+
+        for (id, node) in self.nodes.iter().enumerate() {
+            for &(edge, child) in &node.children {
+                if edge.is_empty() {
+                    writeln!(out, "    n{id} -> n{child};").unwrap();
+                } else {
+                    writeln!(out, "    n{id} -> n{child} [label=\"{}\"];", escape(edge)).unwrap();
+                }
+            }
+        }
+
+        if options.parent_links {
+            for (id, node) in self.nodes.iter().enumerate() {
+                match node.parent {
+                    Some(ParentLink::Node(parent)) => {
+                        writeln!(out, "    n{id} -> n{parent} [style=dashed, color=gray];")
+                            .unwrap();
+                    }
+                    Some(ParentLink::Dangling) => {
+                        writeln!(out, "    dangling{id} [label=\"?\", shape=plaintext];").unwrap();
+                        writeln!(out, "    n{id} -> dangling{id} [style=dashed, color=red];")
+                            .unwrap();
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    /// One line per node, children indented below their parent with
+    /// box-drawing guides. Edge labels prefix the value, metadata follows it
+    /// in brackets and a parent link is shown as `↑` and the parent's value.
+    pub fn to_ascii(&self, options: &RenderOptions) -> String {
+        let heights = self.heights();
+        let mut out = String::new();
+
+        // (node, label of the edge into it, prefix for its line, whether it
+        // is the last sibling); roots have no connector and so no position
+        let mut stack: Vec<(usize, &str, String, Option<bool>)> = self
+            .roots
+            .iter()
+            .rev()
+            .map(|&root| (root, "", String::new(), None))
+            .collect();
+
+        while let Some((id, edge, prefix, last)) = stack.pop() {
+            let node = &self.nodes[id];
+
+            out.push_str(&prefix);
+            let child_prefix = match last {
+                Some(true) => {
+                    out.push_str("└── ");
+                    format!("{prefix}    ")
+                }
+                Some(false) => {
+                    out.push_str("├── ");
+                    format!("{prefix}│   ")
+                }
+                None => prefix,
+            };
+
+            if !edge.is_empty() {
+                write!(out, "{edge}: ").unwrap();
+            }
+            out.push_str(&node.label);
+            if options.metadata {
+                write!(out, " [{}]", self.metadata(id, &heights)).unwrap();
+            }
+            if options.parent_links {
+                match node.parent {
+                    Some(ParentLink::Node(parent)) => {
+                        write!(out, " ↑{}", self.nodes[parent].label).unwrap()
+                    }
+                    Some(ParentLink::Dangling) => out.push_str(" ↑?"),
+                    None => {}
+                }
+            }
+            out.push('\n');
+
+            let count = node.children.len();
+            for (i, &(edge, child)) in node.children.iter().enumerate().rev() {
+                stack.push((child, edge, child_prefix.clone(), Some(i + 1 == count)));
+            }
+        }
+
+        out
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Layout, RenderOptions};
+
+    fn sample() -> Layout {
+        let mut layout = Layout::default();
+        let root = layout.add(None, "b".to_string());
+        let left = layout.add(Some((root, "L")), "a".to_string());
+        let right = layout.add(Some((root, "R")), "\"c\"".to_string());
+        let leaf = layout.add(Some((right, "L")), "d".to_string());
+        layout.annotate(root, "size", 4);
+        layout.link_parent(left, Some(root));
+        layout.link_parent(right, Some(root));
+        layout.link_parent(leaf, None);
+        layout
+    }
+
+    #[test]
+    fn ascii_view() {
+        let layout = sample();
+
+        let plain = "b\n├── L: a\n└── R: \"c\"\n    └── L: d\n";
+        assert_eq!(plain, layout.to_ascii(&RenderOptions::default()));
+
+        let full = RenderOptions {
+            parent_links: true,
+            metadata: true,
+        };
+        let expected = concat!(
+            "b [size=4 height=3]\n",
+            "├── L: a [height=1] ↑b\n",
+            "└── R: \"c\" [height=2] ↑b\n",
+            "    └── L: d [height=1] ↑?\n",
+        );
+        assert_eq!(expected, layout.to_ascii(&full));
+    }
+
+    #[test]
+    fn dot_view() {
+        let layout = sample();
+        let options = RenderOptions {
+            parent_links: true,
+            metadata: false,
+        };
+        let dot = layout.to_dot(&options);
+
+        assert!(dot.starts_with("digraph tree {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert!(dot.contains("n2 [label=\"\\\"c\\\"\"];"));
+        assert!(dot.contains("n0 -> n1 [label=\"L\"];"));
+        assert!(dot.contains("n2 -> n3 [label=\"L\"];"));
+        assert!(dot.contains("n1 -> n0 [style=dashed, color=gray];"));
+        assert!(dot.contains("n3 -> dangling3 [style=dashed, color=red];"));
+        assert!(!dot.contains("height="));
+    }
+
+    #[test]
+    fn forest_and_empty() {
+        let mut layout = Layout::default();
+        assert_eq!("", layout.to_ascii(&RenderOptions::default()));
+
+        let first = layout.add(None, "x".to_string());
+        layout.add(Some((first, "")), "y".to_string());
+        layout.add(None, "z".to_string());
+        assert_eq!("x\n└── y\nz\n", layout.to_ascii(&RenderOptions::default()));
+        assert!(layout
+            .to_dot(&RenderOptions::default())
+            .contains("n0 -> n1;"));
+    }
+}
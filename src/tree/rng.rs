@@ -0,0 +1,18 @@
+/// Xorshift generator behind the treap priorities and the randomized tests.
+/// A fixed seed replays the same sequence, which keeps both deterministic.
+#[derive(Debug, Clone)]
+pub(crate) struct XorShift(u64);
+
+impl XorShift {
+    pub(crate) fn new(seed: u64) -> Self {
+        // an all-zero state would only ever produce zeros
+        Self(seed.max(1))
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
@@ -1,14 +1,21 @@
 use std::{
     cell::RefCell,
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::{Debug, Display},
     rc::{Rc, Weak},
 };
 
+use super::render::{Layout, Render};
+
 type Parent<T> = Weak<RefCell<Node<T>>>;
 type Leaf<T> = Rc<RefCell<Node<T>>>;
 
 #[derive(Debug)]
 pub struct Node<T> {
     pub value: T,
+    /// Whether a word ends at this node.
+    pub end: bool,
     pub parent: Option<Parent<T>>,
     pub left: Option<Leaf<T>>,
     pub middle: Option<Leaf<T>>,
@@ -17,12 +24,7 @@ pub struct Node<T> {
 
 pub struct TenaryTree<T> {
     root: Option<Leaf<T>>,
-}
-
-trait PropTaker<T> {
-    fn middle_val(&self) -> T;
-    fn left_val(&self) -> T;
-    fn right_val(&self) -> T;
+    length: usize,
 }
 
 impl<T> From<Node<T>> for Leaf<T> {
@@ -35,6 +37,7 @@ impl<T> Node<T> {
     fn new(value: T) -> Self {
         Self {
             value,
+            end: false,
             parent: None,
             left: None,
             middle: None,
@@ -43,123 +46,195 @@ impl<T> Node<T> {
     }
 }
 
-impl <T: Copy> PropTaker<T> for Leaf<T> {
-    fn middle_val(&self) -> T {
-        self.borrow().middle.clone().unwrap().borrow().value
+/// The child of `node` that `pick` selects, created with `value` and a
+/// parent link back to `node` if it is missing.
+fn child<T: Copy>(
+    node: &Leaf<T>,
+    pick: fn(&mut Node<T>) -> &mut Option<Leaf<T>>,
+    value: T,
+) -> Leaf<T> {
+    let mut current = node.borrow_mut();
+    pick(&mut current)
+        .get_or_insert_with(|| {
+            let mut child = Node::new(value);
+            child.parent = Some(Rc::downgrade(node));
+            child.into()
+        })
+        .clone()
+}
+
+impl TenaryTree<char> {
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            length: 0,
+        }
     }
 
-    fn left_val(&self) -> T {
-        self.borrow().left.clone().unwrap().borrow().value
+    pub fn len(&self) -> usize {
+        self.length
     }
 
-    fn right_val(&self) -> T {
-        self.borrow().right.clone().unwrap().borrow().value
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
     }
-}
 
-impl TenaryTree<char> {
-    fn new() -> Self {
-        Self {
-            root: None
+    /// Adds `word`, returning `false` if it was already present. The empty
+    /// word has no node to end on and is never stored.
+    pub fn insert(&mut self, word: &str) -> bool {
+        let mut chars = word.chars();
+        let Some(mut c) = chars.next() else {
+            return false;
+        };
+        let mut cursor = self.root.get_or_insert_with(|| Node::new(c).into()).clone();
+
+        loop {
+            let value = cursor.borrow().value;
+            cursor = match c.cmp(&value) {
+                Ordering::Less => child(&cursor, |n| &mut n.left, c),
+                Ordering::Greater => child(&cursor, |n| &mut n.right, c),
+                Ordering::Equal => match chars.next() {
+                    Some(next) => {
+                        c = next;
+                        child(&cursor, |n| &mut n.middle, c)
+                    }
+                    None => break,
+                },
+            };
         }
+
+        let mut last = cursor.borrow_mut();
+        if last.end {
+            return false;
+        }
+        last.end = true;
+        self.length += 1;
+        true
     }
 
-    fn insert(&mut self, word: &str) {
-        let mut cursor: Leaf<char>;
-        let mut chars = word.chars().into_iter();
+    pub fn contains(&self, word: &str) -> bool {
+        let mut chars = word.chars();
+        let Some(mut c) = chars.next() else {
+            return false;
+        };
+        let mut cursor = self.root.clone();
 
-        if self.root.is_none() {
-            let next_char = chars.next();
-            match next_char {
-                Some(c) => {
-                    self.root = Some(Node::new(c).into());
-                    cursor = self.root.clone().unwrap();
-                }
-                _ => {
-                    return;
-                }
+        while let Some(node) = cursor {
+            let current = node.borrow();
+            cursor = match c.cmp(&current.value) {
+                Ordering::Less => current.left.clone(),
+                Ordering::Greater => current.right.clone(),
+                Ordering::Equal => match chars.next() {
+                    Some(next) => {
+                        c = next;
+                        current.middle.clone()
+                    }
+                    None => return current.end,
+                },
             };
+        }
+
+        false
+    }
+}
 
-            while let Some(c) = chars.next() {
-                cursor.borrow_mut().middle = Some(Node::new(c).into());
-                let new_cursor = cursor.borrow().middle.clone().unwrap();
-                cursor = new_cursor;
+impl<T: Copy + Ord + Debug> TenaryTree<T> {
+    /// Checks that every level is ordered as a binary search tree, with the
+    /// middle link starting a fresh level, that every node below the root
+    /// links back to the node holding it, that no path stops short of a word
+    /// end and that the length matches the number of words.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut stack = Vec::new();
+        if let Some(root) = &self.root {
+            if root.borrow().parent.is_some() {
+                return Err("root has a parent".to_string());
             }
-            return;
+            stack.push((root.clone(), None, None));
         }
+        let mut words = 0;
 
-        cursor = self.root.clone().unwrap();
-
-        // find the root
-        while let Some(c) = chars.next() {
-            if cursor.borrow().value == c {
-                if cursor.borrow().middle.is_none() {
-                    cursor.borrow_mut().middle = Some(Node::new(c).into());
-                    let new_cursor = cursor.borrow().middle.clone().unwrap();
-                    cursor = new_cursor;
-                    break;
-                }
-                let new_cursor = cursor.borrow().middle.clone().unwrap();
-                cursor = new_cursor;
-                continue;
+        while let Some((node, lower, upper)) = stack.pop() {
+            let current = node.borrow();
+            let below = lower.is_some_and(|l| current.value <= l);
+            let above = upper.is_some_and(|u| current.value >= u);
+            if below || above {
+                return Err(format!(
+                    "{:?} is out of order within its level",
+                    current.value
+                ));
             }
-            if c > cursor.borrow().value {
-                if cursor.borrow().right.is_none() {
-                    cursor.borrow_mut().right = Some(Node::new(c).into());
-                    let new_cursor = cursor.borrow().right.clone().unwrap();
-                    cursor = new_cursor;
-                    break;
-                }
-                let new_cursor = cursor.borrow().right.clone().unwrap();
-                cursor = new_cursor;
-                continue;
+            if current.end {
+                words += 1;
+            } else if current.middle.is_none() {
+                return Err(format!(
+                    "{:?} ends no word and leads nowhere",
+                    current.value
+                ));
             }
-            if c < cursor.borrow().value {
-                if cursor.borrow().left.is_none() {
-                    cursor.borrow_mut().left = Some(Node::new(c).into());
-                    let new_cursor = cursor.borrow().left.clone().unwrap();
-                    cursor = new_cursor;
-                    break;
+
+            let children = [
+                (&current.left, lower, Some(current.value)),
+                (&current.middle, None, None),
+                (&current.right, Some(current.value), upper),
+            ];
+            for (child, lower, upper) in children {
+                let Some(child) = child else { continue };
+                let parent = child.borrow().parent.as_ref().and_then(Weak::upgrade);
+                if !parent.is_some_and(|p| Rc::ptr_eq(&p, &node)) {
+                    return Err(format!(
+                        "{:?} does not link back to its parent",
+                        child.borrow().value
+                    ));
                 }
-                let new_cursor = cursor.borrow().left.clone().unwrap();
-                cursor = new_cursor;
-                continue;
+                stack.push((child.clone(), lower, upper));
             }
         }
 
-        while let Some(c) = chars.next() {
-            if cursor.borrow().middle.is_none() {
-                cursor.borrow_mut().middle = Some(Node::new(c).into());
-                let new_cursor = cursor.borrow().middle.clone().unwrap();
-                cursor = new_cursor;
-                continue;
-            }
+        if words != self.length {
+            return Err(format!(
+                "tree holds {} words but length is {}",
+                words, self.length
+            ));
+        }
 
-            if cursor.borrow().middle.is_some() && cursor.middle_val() == c {
-                let new_cursor = cursor.borrow().middle.clone().unwrap();
-                cursor = new_cursor;
-                continue;
-            }
+        Ok(())
+    }
+}
 
-            if cursor.borrow().right.is_some() && cursor.right_val() == c {
-                let new_cursor = cursor.borrow().right.clone().unwrap();
-                cursor = new_cursor;
-                continue;
-            }
+impl<T: Display> Render for TenaryTree<T> {
+    /// Lays the tree out with `<`, `=` and `>` edges for the left, middle
+    /// and right links. Parent pointers are resolved after the walk.
+    fn layout(&self) -> Layout {
+        let mut layout = Layout::default();
+        let mut ids = HashMap::new();
+        let mut parents = Vec::new();
+        let mut stack: Vec<_> = self.root.iter().map(|root| (root.clone(), None)).collect();
 
-            if cursor.borrow().left.is_some() && cursor.left_val() == c {
-                let new_cursor = cursor.borrow().left.clone().unwrap();
-                cursor = new_cursor;
-                continue;
+        while let Some((node, under)) = stack.pop() {
+            let n = node.borrow();
+            let id = layout.add(under, n.value.to_string());
+            ids.insert(Rc::as_ptr(&node), id);
+            if let Some(parent) = &n.parent {
+                parents.push((id, parent.upgrade()));
             }
+
+            stack.extend(n.right.clone().map(|right| (right, Some((id, ">")))));
+            stack.extend(n.middle.clone().map(|middle| (middle, Some((id, "=")))));
+            stack.extend(n.left.clone().map(|left| (left, Some((id, "<")))));
+        }
+
+        for (id, parent) in parents {
+            let target = parent.and_then(|p| ids.get(&Rc::as_ptr(&p)).copied());
+            layout.link_parent(id, target);
         }
+        layout
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::TenaryTree;
-
+    use crate::tree::render::{Render, RenderOptions};
 
     #[test]
     fn insert_nodes() {
@@ -168,8 +243,34 @@ mod tests {
         tree.insert("apple");
         tree.insert("peal");
         tree.insert("lemon");
+        tree.insert("apply");
+        tree.insert("apt");
+        assert!(tree.insert("app"));
+        assert!(!tree.insert("apple"));
+        assert!(!tree.insert(""));
+
+        assert_eq!(6, tree.len());
+        assert!(tree.contains("app"));
+        assert!(tree.contains("apply"));
+        assert!(!tree.contains("ap"));
+        assert!(!tree.contains("pear"));
+        assert!(tree.validate().is_ok());
+    }
 
+    #[test]
+    fn render_views() {
+        let mut tree = TenaryTree::new();
+        tree.insert("at");
+        tree.insert("be");
 
-        println!("{:?}", tree.root);
+        let expected = "a\n├── =: t\n└── >: b\n    └── =: e\n";
+        assert_eq!(expected, tree.to_ascii(&RenderOptions::default()));
+
+        let dot = tree.to_dot(&RenderOptions {
+            parent_links: true,
+            metadata: true,
+        });
+        assert!(dot.contains("n0 -> n2 [label=\">\"];"));
+        assert!(dot.contains("height=3"));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1 @@
+pub(crate) use super::rng::XorShift;
@@ -0,0 +1,758 @@
+use std::{
+    cmp::Ordering,
+    collections::hash_map::RandomState,
+    fmt::Debug,
+    hash::{BuildHasher, Hasher},
+    mem,
+    ops::{Bound, RangeBounds},
+};
+
+use super::{
+    comparator::{Comparator, Natural},
+    rng::XorShift,
+};
+
+type Link<V> = Option<Box<Node<V>>>;
+
+/// Node shared by both treaps. `size` serves the implicit keys, and
+/// `reversed` marks a subtree whose children still have to be swapped.
+#[derive(Debug, Clone)]
+pub struct Node<V> {
+    pub value: V,
+    pub priority: u64,
+    pub size: usize,
+    pub reversed: bool,
+
+    pub left: Link<V>,
+    pub right: Link<V>,
+}
+
+/// Randomized search tree: ordered by value like a BST and heap-ordered by a
+/// random priority, which keeps the expected depth at `O(log n)` without any
+/// rotation bookkeeping. Every update is a `split` followed by `merge`s.
+pub struct Treap<V, C = Natural> {
+    root: Link<V>,
+    rng: XorShift,
+    comparator: C,
+}
+
+/// Treap keyed by position instead of value: an indexable sequence with
+/// `O(log n)` insertion, removal and range reversal at any index.
+pub struct ImplicitTreap<V> {
+    root: Link<V>,
+    rng: XorShift,
+}
+
+/// In-order iterator. Pending reversals are applied on the fly by tracking,
+/// per stack entry, whether the children are swapped.
+pub struct Iter<'a, V> {
+    stack: Vec<(&'a Node<V>, bool)>,
+    remaining: usize,
+}
+
+impl XorShift {
+    /// Seeded from the per-process random keys `HashMap` uses.
+    fn from_entropy() -> Self {
+        Self::new(RandomState::new().build_hasher().finish())
+    }
+}
+
+impl<V> Node<V> {
+    fn new(value: V, priority: u64) -> Self {
+        Self {
+            value,
+            priority,
+            size: 1,
+            reversed: false,
+            left: None,
+            right: None,
+        }
+    }
+}
+
+fn size<V>(link: &Link<V>) -> usize {
+    link.as_ref().map_or(0, |n| n.size)
+}
+
+fn update<V>(node: &mut Node<V>) {
+    node.size = 1 + size(&node.left) + size(&node.right);
+}
+
+/// Applies a pending reversal to the children of `node` and hands it down
+/// to them.
+fn push_down<V>(node: &mut Node<V>) {
+    if node.reversed {
+        mem::swap(&mut node.left, &mut node.right);
+        for child in [&mut node.left, &mut node.right].into_iter().flatten() {
+            child.reversed ^= true;
+        }
+        node.reversed = false;
+    }
+}
+
+/// Last value in order, found by walking down the last spine.
+fn last<V>(link: &Link<V>) -> Option<&V> {
+    let mut node = link.as_deref()?;
+    let mut flipped = node.reversed;
+
+    loop {
+        let next = if flipped { &node.left } else { &node.right };
+        match next.as_deref() {
+            Some(next) => {
+                flipped ^= next.reversed;
+                node = next;
+            }
+            None => return Some(&node.value),
+        }
+    }
+}
+
+/// Joins two treaps where every value of `left` comes before every value of
+/// `right`. The root with the higher priority stays on top.
+fn merge<V>(left: Link<V>, right: Link<V>) -> Link<V> {
+    match (left, right) {
+        (None, link) | (link, None) => link,
+        (Some(mut l), Some(mut r)) => {
+            if l.priority > r.priority {
+                push_down(&mut l);
+                l.right = merge(l.right.take(), Some(r));
+                update(&mut l);
+                Some(l)
+            } else {
+                push_down(&mut r);
+                r.left = merge(Some(l), r.left.take());
+                update(&mut r);
+                Some(r)
+            }
+        }
+    }
+}
+
+/// Splits `link` into the values ordered before `key` (or up to and
+/// including it when `inclusive`) and the rest.
+fn split_key<V, C: Comparator<V>>(
+    link: Link<V>,
+    key: &V,
+    inclusive: bool,
+    comparator: &C,
+) -> (Link<V>, Link<V>) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+    push_down(&mut node);
+
+    let goes_left = match comparator.compare(&node.value, key) {
+        Ordering::Less => true,
+        Ordering::Equal => inclusive,
+        Ordering::Greater => false,
+    };
+
+    if goes_left {
+        let (middle, right) = split_key(node.right.take(), key, inclusive, comparator);
+        node.right = middle;
+        update(&mut node);
+        (Some(node), right)
+    } else {
+        let (left, middle) = split_key(node.left.take(), key, inclusive, comparator);
+        node.left = middle;
+        update(&mut node);
+        (left, Some(node))
+    }
+}
+
+/// Splits `link` into its first `index` values and the rest.
+fn split_at<V>(link: Link<V>, index: usize) -> (Link<V>, Link<V>) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+    push_down(&mut node);
+
+    let left_size = size(&node.left);
+    if index <= left_size {
+        let (left, middle) = split_at(node.left.take(), index);
+        node.left = middle;
+        update(&mut node);
+        (left, Some(node))
+    } else {
+        let (middle, right) = split_at(node.right.take(), index - left_size - 1);
+        node.right = middle;
+        update(&mut node);
+        (Some(node), right)
+    }
+}
+
+/// Merges two keyed treaps whose ranges may interleave: the higher-priority
+/// root splits the other treap and both halves are merged recursively. On a
+/// tie the value from the higher-priority side is kept.
+fn union<V, C: Comparator<V>>(a: Link<V>, b: Link<V>, comparator: &C) -> Link<V> {
+    match (a, b) {
+        (None, link) | (link, None) => link,
+        (Some(mut a), Some(mut b)) => {
+            if a.priority < b.priority {
+                mem::swap(&mut a, &mut b);
+            }
+
+            let (b_left, rest) = split_key(Some(b), &a.value, false, comparator);
+            let (_, b_right) = split_key(rest, &a.value, true, comparator);
+            a.left = union(a.left.take(), b_left, comparator);
+            a.right = union(a.right.take(), b_right, comparator);
+            update(&mut a);
+            Some(a)
+        }
+    }
+}
+
+/// Checks the heap order on priorities and the stored sizes below `link`,
+/// returning the number of nodes.
+fn validate_shape<V>(link: &Link<V>) -> Result<usize, String> {
+    let Some(node) = link else {
+        return Ok(0);
+    };
+
+    for child in node.left.iter().chain(node.right.iter()) {
+        if child.priority > node.priority {
+            return Err(format!(
+                "child priority {} outranks its parent's {}",
+                child.priority, node.priority
+            ));
+        }
+    }
+
+    let size = 1 + validate_shape(&node.left)? + validate_shape(&node.right)?;
+    if node.size != size {
+        return Err(format!(
+            "node stores size {} but its subtree holds {}",
+            node.size, size
+        ));
+    }
+
+    Ok(size)
+}
+
+impl<V, C: Default> Default for Treap<V, C> {
+    fn default() -> Self {
+        Self::with_seed(XorShift::from_entropy().next_u64())
+    }
+}
+
+impl<V: PartialOrd> Treap<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<V, C: Default> Treap<V, C> {
+    /// Treap whose priorities come from a generator seeded with `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_comparator(C::default(), seed)
+    }
+}
+
+impl<V, C> Treap<V, C> {
+    pub fn with_comparator(comparator: C, seed: u64) -> Self {
+        Self {
+            root: None,
+            rng: XorShift::new(seed),
+            comparator,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter::new(&self.root)
+    }
+}
+
+impl<V: Debug, C: Comparator<V>> Treap<V, C> {
+    /// Checks that values are strictly ordered, that priorities form a heap
+    /// and that stored sizes are accurate. A keyed treap never carries a
+    /// pending reversal.
+    pub fn validate(&self) -> Result<(), String> {
+        validate_shape(&self.root)?;
+        self.validate_order(&self.root, None, None)
+    }
+
+    fn validate_order(
+        &self,
+        link: &Link<V>,
+        lower: Option<&V>,
+        upper: Option<&V>,
+    ) -> Result<(), String> {
+        let Some(node) = link else {
+            return Ok(());
+        };
+
+        if node.reversed {
+            return Err(format!("{:?} carries a reversal", node.value));
+        }
+
+        let below = lower.is_some_and(|l| self.comparator.compare(&node.value, l).is_le());
+        let above = upper.is_some_and(|u| self.comparator.compare(&node.value, u).is_ge());
+        if below || above {
+            return Err(format!("{:?} is out of order", node.value));
+        }
+
+        self.validate_order(&node.left, lower, Some(&node.value))?;
+        self.validate_order(&node.right, Some(&node.value), upper)
+    }
+}
+
+impl<V, C: Comparator<V>> Treap<V, C> {
+    /// Inserts `value`, returning `false` when an equal value is already
+    /// present.
+    pub fn insert(&mut self, value: V) -> bool {
+        let (left, rest) = split_key(self.root.take(), &value, false, &self.comparator);
+        let (equal, right) = split_key(rest, &value, true, &self.comparator);
+
+        let inserted = equal.is_none();
+        let middle = match equal {
+            Some(node) => Some(node),
+            None => Some(Box::new(Node::new(value, self.rng.next_u64()))),
+        };
+
+        self.root = merge(merge(left, middle), right);
+        inserted
+    }
+
+    pub fn remove(&mut self, value: &V) -> bool {
+        let (left, rest) = split_key(self.root.take(), value, false, &self.comparator);
+        let (equal, right) = split_key(rest, value, true, &self.comparator);
+
+        self.root = merge(left, right);
+        equal.is_some()
+    }
+
+    pub fn contains(&self, value: &V) -> bool {
+        let mut cursor = self.root.as_deref();
+
+        while let Some(node) = cursor {
+            cursor = match self.comparator.compare(value, &node.value) {
+                Ordering::Equal => return true,
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+            };
+        }
+
+        false
+    }
+
+    /// Moves all values of `other` into `self`: a single merge when every
+    /// value of `other` sorts after those of `self`, a union otherwise.
+    pub fn append(&mut self, other: &mut Treap<V, C>) {
+        let other_root = other.root.take();
+        let disjoint = match (last(&self.root), Iter::new(&other_root).next()) {
+            (Some(last), Some(first)) => self.comparator.compare(last, first) == Ordering::Less,
+            _ => true,
+        };
+
+        self.root = if disjoint {
+            merge(self.root.take(), other_root)
+        } else {
+            union(self.root.take(), other_root, &self.comparator)
+        };
+    }
+}
+
+impl<V, C: Comparator<V> + Clone> Treap<V, C> {
+    /// Moves every value greater than or equal to `key` into a new treap.
+    pub fn split_off(&mut self, key: &V) -> Treap<V, C> {
+        let (left, right) = split_key(self.root.take(), key, false, &self.comparator);
+        self.root = left;
+
+        Treap {
+            root: right,
+            rng: XorShift::new(self.rng.next_u64()),
+            comparator: self.comparator.clone(),
+        }
+    }
+}
+
+impl<V> Default for ImplicitTreap<V> {
+    fn default() -> Self {
+        Self::with_seed(XorShift::from_entropy().next_u64())
+    }
+}
+
+impl<V> ImplicitTreap<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sequence whose priorities come from a generator seeded with `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            root: None,
+            rng: XorShift::new(seed),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Inserts `value` so that it ends up at `index`, shifting everything
+    /// after it. Panics when `index > len`, like `Vec::insert`.
+    pub fn insert(&mut self, index: usize, value: V) {
+        let len = self.len();
+        assert!(
+            index <= len,
+            "insertion index {index} is out of bounds for length {len}"
+        );
+
+        let node = Some(Box::new(Node::new(value, self.rng.next_u64())));
+        let (left, right) = split_at(self.root.take(), index);
+        self.root = merge(merge(left, node), right);
+    }
+
+    /// Checks that priorities form a heap and that stored sizes are
+    /// accurate.
+    pub fn validate(&self) -> Result<(), String> {
+        validate_shape(&self.root).map(|_| ())
+    }
+
+    pub fn push(&mut self, value: V) {
+        self.insert(self.len(), value);
+    }
+
+    /// Removes and returns the value at `index`, or `None` when it is out of
+    /// bounds.
+    pub fn remove(&mut self, index: usize) -> Option<V> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let (left, rest) = split_at(self.root.take(), index);
+        let (middle, right) = split_at(rest, 1);
+        self.root = merge(left, right);
+        middle.map(|node| node.value)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&V> {
+        let mut cursor = self.root.as_deref();
+        let mut index = index;
+        let mut flipped = false;
+
+        while let Some(node) = cursor {
+            flipped ^= node.reversed;
+            let (first, second) = if flipped {
+                (&node.right, &node.left)
+            } else {
+                (&node.left, &node.right)
+            };
+
+            let first_size = size(first);
+            cursor = match index.cmp(&first_size) {
+                Ordering::Equal => return Some(&node.value),
+                Ordering::Less => first.as_deref(),
+                Ordering::Greater => {
+                    index -= first_size + 1;
+                    second.as_deref()
+                }
+            };
+        }
+
+        None
+    }
+
+    /// Reverses the values inside `range` in `O(log n)` by flagging the
+    /// subtree that holds them. Panics when the range is out of bounds, like
+    /// slice indexing.
+    pub fn reverse(&mut self, range: impl RangeBounds<usize>) {
+        let len = self.len();
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => len,
+        };
+        assert!(
+            start <= end && end <= len,
+            "range {start}..{end} is out of bounds for length {len}"
+        );
+
+        let (left, rest) = split_at(self.root.take(), start);
+        let (mut middle, right) = split_at(rest, end - start);
+        if let Some(ref mut node) = middle {
+            node.reversed ^= true;
+        }
+        self.root = merge(merge(left, middle), right);
+    }
+
+    /// Moves the values from `at` onwards into a new sequence.
+    pub fn split_off(&mut self, at: usize) -> ImplicitTreap<V> {
+        let (left, right) = split_at(self.root.take(), at);
+        self.root = left;
+
+        ImplicitTreap {
+            root: right,
+            rng: XorShift::new(self.rng.next_u64()),
+        }
+    }
+
+    /// Moves all values of `other` to the end of `self` with a single merge.
+    pub fn append(&mut self, other: &mut ImplicitTreap<V>) {
+        self.root = merge(self.root.take(), other.root.take());
+    }
+
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter::new(&self.root)
+    }
+}
+
+impl<V> FromIterator<V> for ImplicitTreap<V> {
+    fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
+        let mut sequence = Self::new();
+        for value in iter {
+            sequence.push(value);
+        }
+        sequence
+    }
+}
+
+impl<'a, V> Iter<'a, V> {
+    fn new(root: &'a Link<V>) -> Self {
+        let mut iter = Iter {
+            stack: Vec::new(),
+            remaining: size(root),
+        };
+        iter.descend(root, false);
+        iter
+    }
+
+    /// Pushes the first spine of `link`, which is the right one in a subtree
+    /// that is pending reversal.
+    fn descend(&mut self, link: &'a Link<V>, mut flipped: bool) {
+        let mut cursor = link.as_deref();
+
+        while let Some(node) = cursor {
+            flipped ^= node.reversed;
+            self.stack.push((node, flipped));
+            cursor = if flipped {
+                node.right.as_deref()
+            } else {
+                node.left.as_deref()
+            };
+        }
+    }
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, flipped) = self.stack.pop()?;
+        let rest = if flipped { &node.left } else { &node.right };
+        self.descend(rest, flipped);
+
+        self.remaining -= 1;
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<V> ExactSizeIterator for Iter<'_, V> {}
+
+impl<'a, V, C> IntoIterator for &'a Treap<V, C> {
+    type Item = &'a V;
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, V> IntoIterator for &'a ImplicitTreap<V> {
+    type Item = &'a V;
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{ImplicitTreap, Link, Treap};
+    use crate::tree::test_util::XorShift;
+
+    /// Returns the subtree size while asserting the heap order on priorities
+    /// and the stored sizes.
+    fn check_node<V>(link: &Link<V>) -> usize {
+        let Some(node) = link else {
+            return 0;
+        };
+
+        for child in node.left.iter().chain(node.right.iter()) {
+            assert!(child.priority <= node.priority);
+        }
+
+        let size = 1 + check_node(&node.left) + check_node(&node.right);
+        assert_eq!(size, node.size);
+        size
+    }
+
+    fn height<V>(link: &Link<V>) -> usize {
+        link.as_ref()
+            .map_or(0, |n| 1 + height(&n.left).max(height(&n.right)))
+    }
+
+    #[test]
+    fn sorted_inserts_stay_shallow() {
+        let mut treap: Treap<i32> = Treap::with_seed(7);
+        for value in 0..10_000 {
+            assert!(treap.insert(value));
+        }
+
+        assert_eq!(10_000, check_node(&treap.root));
+        // expected depth is about 2.99 * ln(n), around 28 here
+        assert!(height(&treap.root) < 60);
+    }
+
+    #[test]
+    fn same_seed_same_shape() {
+        let build = |seed| {
+            let mut treap: Treap<i32> = Treap::with_seed(seed);
+            for value in [5, 1, 9, 3, 7] {
+                treap.insert(value);
+            }
+            treap
+        };
+
+        let (a, b) = (build(42), build(42));
+        assert_eq!(
+            a.root.as_ref().map(|n| (n.value, n.priority)),
+            b.root.as_ref().map(|n| (n.value, n.priority))
+        );
+    }
+
+    #[test]
+    fn random_operations_match_btreeset() {
+        let mut rng = XorShift::new(0x9E37_79B9_7F4A_7C15);
+        let mut treap: Treap<i32> = Treap::with_seed(1);
+        let mut expected = BTreeSet::new();
+
+        for _ in 0..5000 {
+            let value = (rng.next_u64() % 512) as i32;
+            if rng.next_u64().is_multiple_of(3) {
+                assert_eq!(expected.remove(&value), treap.remove(&value));
+            } else {
+                assert_eq!(expected.insert(value), treap.insert(value));
+            }
+            assert_eq!(expected.contains(&value), treap.contains(&value));
+        }
+
+        assert_eq!(expected.len(), check_node(&treap.root));
+        assert!(treap.iter().eq(expected.iter()));
+    }
+
+    #[test]
+    fn split_off_and_append() {
+        let mut low: Treap<i32> = Treap::with_seed(3);
+        for value in 0..20 {
+            low.insert(value);
+        }
+
+        let mut high = low.split_off(&12);
+        assert_eq!(
+            (0..12).collect::<Vec<_>>(),
+            low.iter().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            (12..20).collect::<Vec<_>>(),
+            high.iter().copied().collect::<Vec<_>>()
+        );
+
+        low.append(&mut high);
+        assert!(high.is_empty());
+        assert_eq!(20, check_node(&low.root));
+
+        let mut evens: Treap<i32> = Treap::with_seed(4);
+        for value in (0..30).step_by(2) {
+            evens.insert(value);
+        }
+        low.append(&mut evens);
+        assert_eq!(25, check_node(&low.root));
+        assert!(low.iter().copied().eq((0..20).chain((20..30).step_by(2))));
+    }
+
+    #[test]
+    fn implicit_insert_remove_and_reverse() {
+        let mut sequence = ImplicitTreap::with_seed(5);
+        for value in ['a', 'b', 'c', 'd', 'e'] {
+            sequence.push(value);
+        }
+
+        sequence.insert(2, 'x');
+        assert_eq!("abxcde", sequence.iter().collect::<String>());
+
+        sequence.reverse(1..5);
+        assert_eq!("adcxbe", sequence.iter().collect::<String>());
+        assert_eq!(Some(&'c'), sequence.get(2));
+        assert_eq!(None, sequence.get(6));
+
+        assert_eq!(Some('x'), sequence.remove(3));
+        assert_eq!(None, sequence.remove(5));
+        sequence.reverse(..);
+        assert_eq!("ebcda", sequence.iter().collect::<String>());
+
+        let mut tail = sequence.split_off(3);
+        assert_eq!("da", tail.iter().collect::<String>());
+        tail.append(&mut sequence);
+        assert_eq!("daebc", tail.iter().collect::<String>());
+    }
+
+    #[test]
+    fn implicit_random_operations_match_vec() {
+        let mut rng = XorShift::new(0x2545_F491_4F6C_DD1D);
+        let mut sequence = ImplicitTreap::with_seed(9);
+        let mut expected = Vec::new();
+
+        for step in 0..3000 {
+            let len = expected.len();
+            match rng.next_u64() % 4 {
+                0 | 1 => {
+                    let index = (rng.next_u64() % (len as u64 + 1)) as usize;
+                    sequence.insert(index, step);
+                    expected.insert(index, step);
+                }
+                2 if len > 0 => {
+                    let index = (rng.next_u64() % len as u64) as usize;
+                    assert_eq!(Some(expected.remove(index)), sequence.remove(index));
+                }
+                _ => {
+                    let a = (rng.next_u64() % (len as u64 + 1)) as usize;
+                    let b = (rng.next_u64() % (len as u64 + 1)) as usize;
+                    let (start, end) = (a.min(b), a.max(b));
+                    sequence.reverse(start..end);
+                    expected[start..end].reverse();
+                }
+            }
+        }
+
+        assert_eq!(expected.len(), check_node(&sequence.root));
+        assert!(sequence.iter().eq(expected.iter()));
+        for (index, value) in expected.iter().enumerate() {
+            assert_eq!(Some(value), sequence.get(index));
+        }
+    }
+}
@@ -1,165 +1,1086 @@
-use std::{cmp::Ordering};
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    fmt::{Debug, Display},
+    mem,
+};
 
+use super::render::{Layout, Render};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct Node {
-    value: char,
-    childs: Vec<Node>,
-    is_end: bool,
+pub struct Node<K, V> {
+    key: K,
+    value: Option<V>,
+    /// Hit count or score of the key ending here, zero without a value.
+    weight: u64,
+    /// Largest weight anywhere in this subtree, this node included.
+    best: u64,
+    childs: Vec<Node<K, V>>,
 }
 
-pub struct Trie {
-    root: Vec<Node>,
+/// Prefix tree mapping sequences of `K` to values. A key is any
+/// `IntoIterator<Item = K>`: the characters of a word, the bytes of a byte
+/// string or the segments of a path. Siblings are kept sorted, so every step
+/// down is a binary search. The key element type comes second so that
+/// `TrieMap<V>` reads as a map from words.
+pub struct TrieMap<V, K = char> {
+    /// Value and weight of the empty key, which has no node of its own.
+    empty: Option<V>,
+    empty_weight: u64,
+    root: Vec<Node<K, V>>,
+    length: usize,
 }
 
-impl Node {
-    fn new(value: char) -> Self {
-        Self { value, childs: Vec::default(), is_end: false }
-    }
+/// Depth-first walk below a prefix, returned by `TrieMap::completions`.
+/// Visiting a node before its children, and the children in order, yields
+/// the keys in lexicographic order. The key being built is kept in a single
+/// buffer and only cloned for the keys that are yielded.
+pub struct Completions<'a, V, K> {
+    /// Value of the prefix itself, yielded before anything below it.
+    pending: Option<&'a V>,
+    /// Nodes still to visit with the depth they sit at.
+    stack: Vec<(&'a Node<K, V>, usize)>,
+    key: Vec<K>,
+    remaining: usize,
+}
+
+/// Entry in the best-first search of `TrieMap::top_k`: a finished key or a
+/// subtree not expanded yet, ranked by the largest weight it can produce and
+/// then by key, so equal weights come out in lexicographic order.
+struct Candidate<'a, K, V> {
+    weight: u64,
+    key: Vec<K>,
+    target: Target<'a, K, V>,
+}
 
-    fn get_value(&self) -> char { self.value }
+enum Target<'a, K, V> {
+    Key(&'a V),
+    Subtree(&'a Node<K, V>),
+}
+
+/// Keys of a `Trie` starting with a prefix, returned by `Trie::completions`.
+pub struct Keys<'a, K>(Completions<'a, (), K>);
+
+/// Set of keys, a `TrieMap` without payloads.
+pub struct Trie<K = char> {
+    map: TrieMap<(), K>,
+}
 
-    fn printf(&self) {
-        println!("Character {:?} -> {:?}", self.value, self.childs.iter().map(Node::get_value).collect::<Vec<_>>());
-        for node in self.childs.iter() {
-            node.printf();
+impl<K, V> Node<K, V> {
+    fn new(key: K) -> Self {
+        Self {
+            key,
+            value: None,
+            weight: 0,
+            best: 0,
+            childs: Vec::default(),
         }
     }
 }
 
-impl PartialOrd for Node {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+impl<K: Ord, V: Eq> PartialOrd for Node<K, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for Node {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        if self.value > other.value { Ordering::Greater }
-        else if self.value < other.value { Ordering::Less }
-        else { Ordering::Equal }
+impl<K: Ord, V: Eq> Ord for Node<K, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
     }
 }
 
-impl Trie {
-    fn new() -> Self {
-        Self { root: Vec::default() }
+/// What `TrieMap::descend` finds at the end of a prefix.
+struct Branch<'a, K, V> {
+    value: &'a Option<V>,
+    weight: u64,
+    childs: &'a [Node<K, V>],
+}
+
+/// Index of the child of `siblings` holding `key`, if any.
+fn child<K: Ord, V>(siblings: &[Node<K, V>], key: &K) -> Option<usize> {
+    siblings.binary_search_by(|node| node.key.cmp(key)).ok()
+}
+
+/// Recomputes the cached subtree maxima along `path`, deepest node first.
+/// Indices past the end of the tree are ignored.
+fn refresh<K, V>(siblings: &mut [Node<K, V>], path: &[usize]) {
+    let Some((&index, rest)) = path.split_first() else {
+        return;
+    };
+    let Some(node) = siblings.get_mut(index) else {
+        return;
+    };
+
+    refresh(&mut node.childs, rest);
+    node.best = node
+        .childs
+        .iter()
+        .map(|child| child.best)
+        .fold(node.weight, u64::max);
+}
+
+/// Splits a `/`-separated path into its segments, ignoring empty ones, so
+/// `"/usr//lib/"` is the same key as `"usr/lib"`.
+pub fn path_segments(path: &str) -> impl Iterator<Item = String> + '_ {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+}
+
+impl<V, K> Default for TrieMap<V, K> {
+    fn default() -> Self {
+        Self {
+            empty: None,
+            empty_weight: 0,
+            root: Vec::default(),
+            length: 0,
+        }
+    }
+}
+
+impl<V, K: Ord> TrieMap<V, K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Stores `value` under `key`, returning the value it replaces. A new key
+    /// starts with weight zero and a replaced one keeps its weight.
+    pub fn insert(&mut self, key: impl IntoIterator<Item = K>, value: V) -> Option<V> {
+        let mut slot = &mut self.empty;
+        let mut siblings = &mut self.root;
+
+        for item in key {
+            let index = match siblings.binary_search_by(|node| node.key.cmp(&item)) {
+                Ok(index) => index,
+                Err(index) => {
+                    siblings.insert(index, Node::new(item));
+                    index
+                }
+            };
+            let node = &mut siblings[index];
+            slot = &mut node.value;
+            siblings = &mut node.childs;
+        }
+
+        let previous = slot.replace(value);
+        if previous.is_none() {
+            self.length += 1;
+        }
+        previous
+    }
+
+    /// The value slot at the end of `prefix` and the children below it, or
+    /// `None` if no node spells out the prefix.
+    fn descend(&self, prefix: impl IntoIterator<Item = K>) -> Option<Branch<'_, K, V>> {
+        let mut branch = Branch {
+            value: &self.empty,
+            weight: self.empty_weight,
+            childs: &self.root,
+        };
+
+        for item in prefix {
+            let node = &branch.childs[child(branch.childs, &item)?];
+            branch = Branch {
+                value: &node.value,
+                weight: node.weight,
+                childs: &node.childs,
+            };
+        }
+
+        Some(branch)
+    }
+
+    pub fn get(&self, key: impl IntoIterator<Item = K>) -> Option<&V> {
+        self.descend(key)?.value.as_ref()
+    }
+
+    pub fn get_mut(&mut self, key: impl IntoIterator<Item = K>) -> Option<&mut V> {
+        let mut slot = &mut self.empty;
+        let mut siblings = &mut self.root;
+
+        for item in key {
+            let index = child(siblings, &item)?;
+            let node = &mut siblings[index];
+            slot = &mut node.value;
+            siblings = &mut node.childs;
+        }
+
+        slot.as_mut()
+    }
+
+    pub fn contains_key(&self, key: impl IntoIterator<Item = K>) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Whether any key starts with `prefix`.
+    pub fn starts_with(&self, prefix: impl IntoIterator<Item = K>) -> bool {
+        self.descend(prefix)
+            .is_some_and(|branch| branch.value.is_some() || !branch.childs.is_empty())
+    }
+
+    /// Child indices leading from the root to the node that spells out
+    /// `key`, or `None` if there is no such node.
+    fn path(&self, key: impl IntoIterator<Item = K>) -> Option<Vec<usize>> {
+        let mut path = Vec::new();
+        let mut siblings = &self.root;
+
+        for item in key {
+            let index = child(siblings, &item)?;
+            path.push(index);
+            siblings = &siblings[index].childs;
+        }
+
+        Some(path)
+    }
+
+    /// The node at the end of `path`, or `None` for the empty path.
+    fn node_mut(&mut self, path: &[usize]) -> Option<&mut Node<K, V>> {
+        let (&last, above) = path.split_last()?;
+        let mut siblings = &mut self.root;
+        for &index in above {
+            siblings = &mut siblings[index].childs;
+        }
+        Some(&mut siblings[last])
+    }
+
+    /// Removes `key` and returns its value. Nodes left holding no value and
+    /// leading to no other key are pruned, so the trie never keeps dead
+    /// branches.
+    pub fn remove(&mut self, key: impl IntoIterator<Item = K>) -> Option<V> {
+        let path = self.path(key)?;
+
+        let value = match self.node_mut(&path) {
+            Some(node) => {
+                node.weight = 0;
+                node.value.take()
+            }
+            None => {
+                self.empty_weight = 0;
+                self.empty.take()
+            }
+        }?;
+
+        self.length -= 1;
+        let kept = self.prune(&path);
+        refresh(&mut self.root, &path[..kept]);
+        Some(value)
     }
 
-    fn insert(&mut self, word: &str) {
-        let mut cursor: &mut Node;
-        let mut items = word.chars();
-        let first_char = items.next();
+    /// Removes every key that starts with `prefix`, dropping the whole
+    /// subtree at once, and returns how many keys went with it.
+    pub fn remove_prefix(&mut self, prefix: impl IntoIterator<Item = K>) -> usize {
+        let Some(path) = self.path(prefix) else {
+            return 0;
+        };
 
-        self.root.sort();
+        if path.is_empty() {
+            let removed = self.length;
+            *self = Self::default();
+            return removed;
+        }
 
-        if let Ok(index) = self.root.binary_search(&Node::new(first_char.clone().unwrap())) {
-            cursor = self.root.get_mut(index).unwrap();
-        } else {
-            self.root.push(Node::new(first_char.unwrap()));
-            cursor = self.root.last_mut().unwrap();
+        let node = self.node_mut(&path).expect("the path is not empty");
+        node.weight = 0;
+        let mut removed = usize::from(node.value.take().is_some());
+        let mut stack = mem::take(&mut node.childs);
+        while let Some(node) = stack.pop() {
+            removed += usize::from(node.value.is_some());
+            stack.extend(node.childs);
         }
 
+        self.length -= removed;
+        let kept = self.prune(&path);
+        refresh(&mut self.root, &path[..kept]);
+        removed
+    }
+
+    /// Drops the longest tail of `path` whose nodes hold no value and lead
+    /// to nothing but the next node on the path. Returns how many nodes of
+    /// the path are left.
+    fn prune(&mut self, path: &[usize]) -> usize {
+        let mut nodes = Vec::with_capacity(path.len());
+        let mut siblings = &self.root;
+        for &index in path {
+            let node = &siblings[index];
+            nodes.push(node);
+            siblings = &node.childs;
+        }
 
-        while let Some(item) = items.next() {
-            cursor.childs.sort();
-            if let Ok(next_node) = cursor.childs.binary_search(&Node::new(item.clone())) {
-                cursor = cursor.childs.get_mut(next_node).unwrap();
-            } else {
-                cursor.childs.push(Node::new(item));
-                cursor = cursor.childs.last_mut().unwrap();
+        // walking up, a node is only reached once the child below it on the
+        // path has been cut
+        let mut cut = path.len();
+        for (depth, node) in nodes.iter().enumerate().rev() {
+            let kept = node.childs.len() - usize::from(depth + 1 < path.len());
+            if node.value.is_some() || kept > 0 {
+                break;
             }
+            cut = depth;
+        }
+
+        if cut == path.len() {
+            return cut;
+        }
+
+        let mut siblings = &mut self.root;
+        for &index in &path[..cut] {
+            siblings = &mut siblings[index].childs;
         }
+        siblings.remove(path[cut]);
+        cut
+    }
+
+    pub fn weight(&self, key: impl IntoIterator<Item = K>) -> Option<u64> {
+        let branch = self.descend(key)?;
+        branch.value.as_ref().map(|_| branch.weight)
+    }
+
+    /// Sets the weight `top_k` ranks `key` by. Returns `false` if the key is
+    /// absent.
+    pub fn set_weight(&mut self, key: impl IntoIterator<Item = K>, weight: u64) -> bool {
+        self.reweigh(key, |_| weight).is_some()
+    }
 
-        cursor.is_end = true;
+    /// Adds one to the weight of `key` and returns the new weight, or `None`
+    /// if the key is absent.
+    pub fn record_hit(&mut self, key: impl IntoIterator<Item = K>) -> Option<u64> {
+        self.reweigh(key, |weight| weight.saturating_add(1))
     }
 
-    fn traverse_tree(&self, node: &Node, collector: &mut Vec<String>) -> Vec<String> {
-        let mut inner_collector = Vec::new();
-        collector.push(node.value.to_string());
+    fn reweigh(
+        &mut self,
+        key: impl IntoIterator<Item = K>,
+        f: impl FnOnce(u64) -> u64,
+    ) -> Option<u64> {
+        let path = self.path(key)?;
+
+        let Some(node) = self.node_mut(&path) else {
+            self.empty.as_ref()?;
+            self.empty_weight = f(self.empty_weight);
+            return Some(self.empty_weight);
+        };
 
-        if node.childs.len() < 1 {
-            return Vec::from_iter([collector.join("")]);
+        node.value.as_ref()?;
+        node.weight = f(node.weight);
+        let weight = node.weight;
+        refresh(&mut self.root, &path);
+        Some(weight)
+    }
+}
+
+impl<V, K: Ord + Clone> TrieMap<V, K> {
+    /// Lazy iterator over every key that starts with `prefix` together with
+    /// its value, in lexicographic order. An absent prefix yields nothing.
+    pub fn completions(&self, prefix: impl IntoIterator<Item = K>) -> Completions<'_, V, K> {
+        let key: Vec<K> = prefix.into_iter().collect();
+        let Some(Branch { value, childs, .. }) = self.descend(key.iter().cloned()) else {
+            return Completions {
+                pending: None,
+                stack: Vec::new(),
+                key,
+                remaining: 0,
+            };
+        };
+
+        let depth = key.len();
+        Completions {
+            pending: value.as_ref(),
+            stack: childs.iter().rev().map(|node| (node, depth)).collect(),
+            key,
+            remaining: usize::MAX,
         }
+    }
+
+    /// Every key that starts with `prefix` together with its value, in
+    /// lexicographic order.
+    pub fn auto_complete(&self, prefix: impl IntoIterator<Item = K>) -> Vec<(Vec<K>, &V)> {
+        self.completions(prefix).collect()
+    }
+
+    /// The `k` highest-weighted keys that start with `prefix`, heaviest
+    /// first and equal weights in lexicographic order.
+    ///
+    /// The search is best-first over the cached subtree maxima: a subtree is
+    /// only opened once nothing outside it can outrank its best key, so the
+    /// walk stops after visiting the paths to the `k` results and their
+    /// siblings rather than the whole subtree.
+    pub fn top_k(&self, prefix: impl IntoIterator<Item = K>, k: usize) -> Vec<(Vec<K>, &V)> {
+        let prefix: Vec<K> = prefix.into_iter().collect();
+        let mut found = Vec::new();
+        let Some(branch) = self.descend(prefix.iter().cloned()) else {
+            return found;
+        };
 
-        for n in node.childs.iter() {
-            let result = self.traverse_tree(n, &mut Vec::from_iter([collector.join("")]));
-            inner_collector.extend(result);
+        let mut heap = BinaryHeap::new();
+        if let Some(value) = branch.value {
+            heap.push(Candidate {
+                weight: branch.weight,
+                key: prefix.clone(),
+                target: Target::Key(value),
+            });
         }
+        for node in branch.childs {
+            let mut key = prefix.clone();
+            key.push(node.key.clone());
+            heap.push(Candidate {
+                weight: node.best,
+                key,
+                target: Target::Subtree(node),
+            });
+        }
+
+        while found.len() < k {
+            let Some(candidate) = heap.pop() else {
+                break;
+            };
 
-        if node.is_end {
-            inner_collector.push(collector.join(""));
+            let node = match candidate.target {
+                Target::Key(value) => {
+                    found.push((candidate.key, value));
+                    continue;
+                }
+                Target::Subtree(node) => node,
+            };
+
+            if let Some(value) = &node.value {
+                heap.push(Candidate {
+                    weight: node.weight,
+                    key: candidate.key.clone(),
+                    target: Target::Key(value),
+                });
+            }
+            for child in &node.childs {
+                let mut key = candidate.key.clone();
+                key.push(child.key.clone());
+                heap.push(Candidate {
+                    weight: child.best,
+                    key,
+                    target: Target::Subtree(child),
+                });
+            }
         }
 
-        return inner_collector;
+        found
     }
+}
 
-    fn auto_complete(&self, word: &str) -> Vec<String> {
-        // let mut collector = word.to_string();
-        let mut items = word.chars();
-        let first_char = items.next();
-        let mut cursor: &Node;
+impl<V, K: Ord + Debug> TrieMap<V, K> {
+    /// Checks that siblings are strictly increasing, that every leaf holds a
+    /// value, that only keys carry weight, that the cached subtree maxima are
+    /// accurate and that the length matches the number of values.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut stack: Vec<&[Node<K, V>]> = vec![&self.root];
+        let mut count = usize::from(self.empty.is_some());
 
-        if let Ok(index) = self.root.binary_search(&Node::new(first_char.clone().unwrap())) {
-            cursor = self.root.get(index).unwrap();
-        } else {
-            return Vec::from_iter([word.to_string()]);
+        if self.empty.is_none() && self.empty_weight != 0 {
+            return Err("the absent empty key has a weight".to_string());
         }
 
-        for item in items {
-            if let Ok(index) = cursor.childs.binary_search(&Node::new(item.clone())) {
-                cursor = cursor.childs.get(index).unwrap();
+        while let Some(siblings) = stack.pop() {
+            for pair in siblings.windows(2) {
+                if pair[0].key >= pair[1].key {
+                    return Err(format!(
+                        "siblings {:?} and {:?} are out of order",
+                        pair[0].key, pair[1].key
+                    ));
+                }
             }
+            for node in siblings {
+                if node.childs.is_empty() && node.value.is_none() {
+                    return Err(format!("leaf {:?} does not end a key", node.key));
+                }
+                if node.value.is_none() && node.weight != 0 {
+                    return Err(format!("{:?} has a weight but ends no key", node.key));
+                }
+                let best = node
+                    .childs
+                    .iter()
+                    .map(|child| child.best)
+                    .fold(node.weight, u64::max);
+                if node.best != best {
+                    return Err(format!(
+                        "{:?} caches best weight {} but its subtree's is {}",
+                        node.key, node.best, best
+                    ));
+                }
+                count += usize::from(node.value.is_some());
+                stack.push(&node.childs);
+            }
+        }
+
+        if count != self.length {
+            return Err(format!(
+                "trie holds {} keys but length is {}",
+                count, self.length
+            ));
         }
 
-        let mut collector = Vec::<String>::new();
+        Ok(())
+    }
+}
+
+impl<V, K: Ord, I: IntoIterator<Item = K>> FromIterator<(I, V)> for TrieMap<V, K> {
+    fn from_iter<T: IntoIterator<Item = (I, V)>>(entries: T) -> Self {
+        let mut map = Self::new();
+        for (key, value) in entries {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<V, K: Display> Render for TrieMap<V, K> {
+    /// Lays the trie out as a forest, one root per first element, marking on
+    /// every node whether a key ends there.
+    fn layout(&self) -> Layout {
+        let mut layout = Layout::default();
+        let mut stack: Vec<(&Node<K, V>, Option<usize>)> =
+            self.root.iter().rev().map(|node| (node, None)).collect();
+
+        while let Some((node, under)) = stack.pop() {
+            let id = layout.add(under.map(|parent| (parent, "")), node.key.to_string());
+            layout.annotate(id, "end", node.value.is_some());
+            stack.extend(node.childs.iter().rev().map(|child| (child, Some(id))));
+        }
+
+        layout
+    }
+}
+
+impl<V, K> Completions<'_, V, K> {
+    /// Stops after at most `n` more completions, so a caller that only shows
+    /// the first few never walks the rest of the subtree.
+    pub fn limit(mut self, n: usize) -> Self {
+        self.remaining = self.remaining.min(n);
+        self
+    }
+}
+
+impl<'a, V, K: Clone> Iterator for Completions<'a, V, K> {
+    type Item = (Vec<K>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let value = match self.pending.take() {
+            Some(value) => value,
+            None => loop {
+                let (node, depth) = self.stack.pop()?;
+                self.key.truncate(depth);
+                self.key.push(node.key.clone());
+                self.stack
+                    .extend(node.childs.iter().rev().map(|child| (child, depth + 1)));
+
+                if let Some(value) = &node.value {
+                    break value;
+                }
+            },
+        };
+
+        self.remaining -= 1;
+        Some((self.key.clone(), value))
+    }
+}
+
+impl<K: Ord, V> Ord for Candidate<'_, K, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let is_key = |candidate: &Self| matches!(candidate.target, Target::Key(_));
+        self.weight
+            .cmp(&other.weight)
+            .then_with(|| other.key.cmp(&self.key))
+            .then_with(|| is_key(self).cmp(&is_key(other)))
+    }
+}
+
+impl<K: Ord, V> PartialOrd for Candidate<'_, K, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, V> PartialEq for Candidate<'_, K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl<K: Ord, V> Eq for Candidate<'_, K, V> {}
+
+impl<K> Keys<'_, K> {
+    /// Stops after at most `n` more keys.
+    pub fn limit(self, n: usize) -> Self {
+        Self(self.0.limit(n))
+    }
+}
+
+impl<K: Clone> Iterator for Keys<'_, K> {
+    type Item = Vec<K>;
 
-        if cursor.is_end {
-            collector.push(word.to_string());
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, _)| key)
+    }
+}
+
+impl<K> Default for Trie<K> {
+    fn default() -> Self {
+        Self {
+            map: TrieMap::default(),
         }
-        for item in cursor.childs.iter() {
-            let items = self.traverse_tree(item, &mut Vec::<String>::from_iter([word.to_string()]));
-            collector.extend(items);
+    }
+}
+
+impl<K: Ord> Trie<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Adds `key`, returning `false` if it was already present.
+    pub fn insert(&mut self, key: impl IntoIterator<Item = K>) -> bool {
+        self.map.insert(key, ()).is_none()
+    }
+
+    pub fn contains(&self, key: impl IntoIterator<Item = K>) -> bool {
+        self.map.contains_key(key)
+    }
+
+    pub fn starts_with(&self, prefix: impl IntoIterator<Item = K>) -> bool {
+        self.map.starts_with(prefix)
+    }
+
+    /// Removes `key`, pruning the nodes that no longer lead to any key.
+    /// Returns `false` if the key was absent.
+    pub fn remove(&mut self, key: impl IntoIterator<Item = K>) -> bool {
+        self.map.remove(key).is_some()
+    }
+
+    /// Removes every key that starts with `prefix` and returns how many
+    /// there were.
+    pub fn remove_prefix(&mut self, prefix: impl IntoIterator<Item = K>) -> usize {
+        self.map.remove_prefix(prefix)
+    }
+
+    pub fn weight(&self, key: impl IntoIterator<Item = K>) -> Option<u64> {
+        self.map.weight(key)
+    }
+
+    /// Sets the weight `top_k` ranks `key` by. Returns `false` if the key is
+    /// absent.
+    pub fn set_weight(&mut self, key: impl IntoIterator<Item = K>, weight: u64) -> bool {
+        self.map.set_weight(key, weight)
+    }
+
+    /// Counts one more hit for `key` and returns its new weight, or `None`
+    /// if the key is absent.
+    pub fn record_hit(&mut self, key: impl IntoIterator<Item = K>) -> Option<u64> {
+        self.map.record_hit(key)
+    }
+}
+
+impl<K: Ord + Clone> Trie<K> {
+    /// Lazy iterator over every key that starts with `prefix`, in
+    /// lexicographic order. An absent prefix yields nothing.
+    pub fn completions(&self, prefix: impl IntoIterator<Item = K>) -> Keys<'_, K> {
+        Keys(self.map.completions(prefix))
+    }
+
+    /// Every key that starts with `prefix`, in lexicographic order.
+    pub fn auto_complete(&self, prefix: impl IntoIterator<Item = K>) -> Vec<Vec<K>> {
+        self.completions(prefix).collect()
+    }
+
+    /// The `k` highest-weighted keys that start with `prefix`, see
+    /// `TrieMap::top_k`.
+    pub fn top_k(&self, prefix: impl IntoIterator<Item = K>, k: usize) -> Vec<Vec<K>> {
+        self.map
+            .top_k(prefix, k)
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect()
+    }
+}
+
+impl<K: Ord + Debug> Trie<K> {
+    pub fn validate(&self) -> Result<(), String> {
+        self.map.validate()
+    }
+}
+
+impl<K: Ord, I: IntoIterator<Item = K>> FromIterator<I> for Trie<K> {
+    fn from_iter<T: IntoIterator<Item = I>>(keys: T) -> Self {
+        Self {
+            map: keys.into_iter().map(|key| (key, ())).collect(),
         }
+    }
+}
+
+impl Trie<char> {
+    /// Trie over the characters of `words`.
+    pub fn from_strs<'a>(words: impl IntoIterator<Item = &'a str>) -> Self {
+        words.into_iter().map(str::chars).collect()
+    }
+}
 
-        collector
+impl Trie<u8> {
+    /// Trie over the bytes of `keys`.
+    pub fn from_bytes<'a>(keys: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        keys.into_iter().map(|key| key.iter().copied()).collect()
+    }
+}
+
+impl Trie<String> {
+    /// Trie over the segments of `/`-separated paths, see `path_segments`.
+    pub fn from_paths<'a>(paths: impl IntoIterator<Item = &'a str>) -> Self {
+        paths.into_iter().map(path_segments).collect()
+    }
+}
+
+impl<K: Display> Render for Trie<K> {
+    fn layout(&self) -> Layout {
+        self.map.layout()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Trie, Node};
+    use super::{path_segments, Node, Trie, TrieMap};
+    use crate::tree::{
+        render::{Render, RenderOptions},
+        test_util::XorShift,
+    };
 
     #[test]
     fn binary_search() {
-        let node1 = Node::new('h');
+        let node1: Node<char, ()> = Node::new('h');
         let node2 = Node::new('c');
         let node3 = Node::new('a');
 
-        let mut root = Vec::new();
-        root.push(node1);
-        root.push(node2);
-        root.push(node3);
-
+        let mut root = [node1, node2, node3];
         root.sort();
 
-        println!("{root:?}");
+        assert_eq!(
+            vec!['a', 'c', 'h'],
+            root.iter().map(|node| node.key).collect::<Vec<_>>()
+        );
     }
 
     #[test]
     fn insert_nodes() {
         let mut trie = Trie::new();
 
-        trie.insert("hello");
-        trie.insert("help");
-        trie.insert("hell");
-        trie.insert("application");
-        trie.insert("applications");
-        trie.insert("applicationssss");
-        trie.insert("apple");
+        trie.insert("hello".chars());
+        trie.insert("help".chars());
+        trie.insert("hell".chars());
+        trie.insert("application".chars());
+        trie.insert("applications".chars());
+        trie.insert("applicationssss".chars());
+        trie.insert("apple".chars());
+        assert!(!trie.insert("help".chars()));
+
+        assert!(trie.validate().is_ok());
+        assert_eq!(7, trie.len());
+        let words: Vec<String> = trie
+            .auto_complete("app".chars())
+            .into_iter()
+            .map(String::from_iter)
+            .collect();
+
+        assert_eq!(
+            vec!["apple", "application", "applications", "applicationssss"],
+            words
+        );
+        assert!(trie.contains("hell".chars()));
+        assert!(!trie.contains("hel".chars()));
+        assert!(trie.starts_with("hel".chars()));
+        assert!(trie.auto_complete("xyz".chars()).is_empty());
+        assert_eq!(7, trie.auto_complete("".chars()).len());
+    }
+
+    #[test]
+    fn trie_map() {
+        let mut map: TrieMap<u32> = TrieMap::new();
+
+        assert_eq!(None, map.insert("tea".chars(), 1));
+        assert_eq!(None, map.insert("ten".chars(), 2));
+        assert_eq!(None, map.insert("te".chars(), 3));
+        assert_eq!(Some(1), map.insert("tea".chars(), 4));
+        assert_eq!(3, map.len());
+
+        assert_eq!(Some(&4), map.get("tea".chars()));
+        assert_eq!(None, map.get("t".chars()));
+        assert!(map.contains_key("te".chars()));
+        assert!(!map.contains_key("tent".chars()));
+
+        *map.get_mut("ten".chars()).unwrap() += 10;
+        assert_eq!(Some(&12), map.get("ten".chars()));
+        assert_eq!(None, map.get_mut("x".chars()));
+
+        // the empty key has no node but is still a key
+        assert_eq!(None, map.insert("".chars(), 0));
+        assert_eq!(Some(&0), map.get("".chars()));
+        assert_eq!(4, map.len());
+
+        assert_eq!(
+            vec![
+                ("te".chars().collect::<Vec<_>>(), &3),
+                ("tea".chars().collect(), &4)
+            ],
+            map.auto_complete("te".chars())[..2]
+        );
+        assert!(map.validate().is_ok());
+    }
+
+    #[test]
+    fn trie_map_remove_prunes() {
+        let mut map: TrieMap<u32> = [("tea", 1), ("ten", 2), ("te", 3), ("inn", 4)]
+            .into_iter()
+            .map(|(key, value)| (key.chars(), value))
+            .collect();
+
+        assert_eq!(None, map.remove("t".chars()));
+        assert_eq!(None, map.remove("teas".chars()));
+        assert_eq!(Some(2), map.remove("ten".chars()));
+        assert_eq!(None, map.remove("ten".chars()));
+        assert!(map.validate().is_ok());
 
-        let words = trie.auto_complete("app");
+        // "te" still leads to "tea", so only the value goes
+        assert_eq!(Some(3), map.remove("te".chars()));
+        assert!(map.starts_with("te".chars()));
+        assert!(map.validate().is_ok());
 
-        println!("{words:?}");
+        // the whole "inn" branch goes, and so does "tea"'s
+        assert_eq!(Some(4), map.remove("inn".chars()));
+        assert_eq!(Some(1), map.remove("tea".chars()));
+        assert!(!map.starts_with("t".chars()));
+        assert!(map.is_empty());
+        assert!(map.validate().is_ok());
+        assert_eq!(0, map.layout().len());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn completions() {
+        let trie = Trie::from_strs(["apple", "ape", "apricot", "ap", "banana", "apex"]);
+        let complete = |prefix: &str, limit: usize| -> Vec<String> {
+            trie.completions(prefix.chars())
+                .limit(limit)
+                .map(String::from_iter)
+                .collect()
+        };
+
+        assert_eq!(
+            vec!["ap", "ape", "apex", "apple", "apricot"],
+            complete("ap", usize::MAX)
+        );
+        // a missing character ends the search instead of being skipped
+        assert!(complete("apz", usize::MAX).is_empty());
+        assert!(complete("x", usize::MAX).is_empty());
+        assert_eq!(vec!["ap", "ape"], complete("ap", 2));
+        assert!(complete("ap", 0).is_empty());
+        assert_eq!(6, complete("", usize::MAX).len());
+        assert_eq!(vec!["banana"], complete("banana", 10));
+
+        let map: TrieMap<usize> = ["b", "a", "ab"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, key)| (key.chars(), i))
+            .collect();
+        let entries: Vec<_> = map.completions("".chars()).limit(10).collect();
+        assert_eq!(
+            vec![(vec!['a'], &1), (vec!['a', 'b'], &2), (vec!['b'], &0)],
+            entries
+        );
+    }
+
+    #[test]
+    fn remove_words() {
+        let mut trie = Trie::from_strs(["car", "cart", "carts", "cat", "dog"]);
+
+        assert!(trie.remove("cart".chars()));
+        assert!(!trie.remove("cart".chars()));
+        assert!(!trie.remove("ca".chars()));
+        assert!(trie.contains("carts".chars()));
+        assert!(trie.validate().is_ok());
+
+        assert!(trie.remove("carts".chars()));
+        assert!(!trie.starts_with("cart".chars()));
+        assert!(trie.contains("car".chars()));
+        assert_eq!(3, trie.len());
+        assert!(trie.validate().is_ok());
+    }
+
+    #[test]
+    fn remove_prefix() {
+        let mut trie = Trie::from_strs(["car", "cart", "carts", "cat", "dog", "do"]);
+
+        assert_eq!(0, trie.remove_prefix("cab".chars()));
+        assert_eq!(3, trie.remove_prefix("car".chars()));
+        assert!(!trie.starts_with("car".chars()));
+        assert!(trie.contains("cat".chars()));
+        assert!(trie.validate().is_ok());
+
+        // the removed subtree takes its now empty ancestors with it
+        assert_eq!(1, trie.remove_prefix("cat".chars()));
+        assert!(!trie.starts_with("c".chars()));
+        assert_eq!(2, trie.len());
+        assert!(trie.validate().is_ok());
+
+        assert_eq!(1, trie.remove_prefix("dog".chars()));
+        assert!(trie.contains("do".chars()));
+        assert!(trie.validate().is_ok());
+
+        trie.insert("".chars());
+        assert_eq!(2, trie.remove_prefix("".chars()));
+        assert!(trie.is_empty());
+        assert!(trie.validate().is_ok());
+    }
+
+    #[test]
+    fn top_k_by_weight() {
+        let mut trie = Trie::from_strs(["car", "card", "care", "cart", "cat", "dog"]);
+        let top = |trie: &Trie, prefix: &str, k: usize| -> Vec<String> {
+            trie.top_k(prefix.chars(), k)
+                .into_iter()
+                .map(String::from_iter)
+                .collect()
+        };
+
+        // without hits every weight ties, so the order is alphabetical
+        assert_eq!(vec!["car", "card", "care"], top(&trie, "ca", 3));
+
+        for (word, hits) in [("cart", 5), ("cat", 3), ("care", 3), ("dog", 9)] {
+            for _ in 0..hits {
+                trie.record_hit(word.chars());
+            }
+        }
+        assert_eq!(Some(5), trie.weight("cart".chars()));
+        assert_eq!(None, trie.record_hit("ca".chars()));
+        assert!(trie.validate().is_ok());
+
+        assert_eq!(vec!["cart", "care", "cat"], top(&trie, "ca", 3));
+        assert_eq!(vec!["dog", "cart"], top(&trie, "", 2));
+        assert_eq!(6, top(&trie, "", 100).len());
+        assert!(top(&trie, "cz", 3).is_empty());
+        assert!(top(&trie, "ca", 0).is_empty());
+
+        // lowering or removing the heaviest key updates the cached maxima
+        assert!(trie.set_weight("cart".chars(), 1));
+        assert!(!trie.set_weight("ca".chars(), 1));
+        assert!(trie.remove("dog".chars()));
+        assert!(trie.validate().is_ok());
+        assert_eq!(vec!["care", "cat", "cart"], top(&trie, "", 3));
+
+        assert_eq!(4, trie.remove_prefix("car".chars()));
+        assert!(trie.validate().is_ok());
+        assert_eq!(vec!["cat"], top(&trie, "", 3));
+    }
+
+    #[test]
+    fn top_k_matches_full_sort() {
+        let mut rng = XorShift::new(21);
+        let mut map: TrieMap<usize, u8> = TrieMap::new();
+
+        for i in 0..2_000 {
+            let len = 1 + rng.next_u64() % 5;
+            let key: Vec<u8> = (0..len)
+                .map(|_| b'a' + (rng.next_u64() % 4) as u8)
+                .collect();
+            match rng.next_u64() % 4 {
+                0 => {
+                    map.remove(key);
+                }
+                1 => {
+                    map.set_weight(key, rng.next_u64() % 50);
+                }
+                _ => {
+                    map.insert(key.clone(), i);
+                    map.record_hit(key);
+                }
+            }
+
+            if i % 100 == 0 {
+                map.validate().unwrap();
+                for prefix in [&b""[..], b"a", b"bc"] {
+                    let mut expected: Vec<_> = map
+                        .completions(prefix.iter().copied())
+                        .map(|(key, _)| (map.weight(key.iter().copied()).unwrap(), key))
+                        .collect();
+                    expected.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+                    expected.truncate(7);
+
+                    let keys: Vec<_> = map
+                        .top_k(prefix.iter().copied(), 7)
+                        .into_iter()
+                        .map(|(key, _)| key)
+                        .collect();
+                    assert_eq!(
+                        expected.into_iter().map(|(_, key)| key).collect::<Vec<_>>(),
+                        keys
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn byte_and_token_keys() {
+        let trie = Trie::from_bytes([&b"\x00\xff"[..], b"\x00", b"ab"]);
+        assert!(trie.contains([0u8, 255]));
+        assert!(trie.contains(*b"ab"));
+        assert!(!trie.contains(*b"a"));
+        assert_eq!(vec![vec![0u8], vec![0, 255]], trie.auto_complete([0u8]));
+
+        let tokens: Trie<u32> = [vec![7, 3, 9], vec![7, 3], vec![1]].into_iter().collect();
+        assert!(tokens.contains([7, 3]));
+        assert_eq!(vec![vec![7, 3], vec![7, 3, 9]], tokens.auto_complete([7]));
+        assert!(tokens.validate().is_ok());
+    }
+
+    #[test]
+    fn path_keys() {
+        let trie = Trie::from_paths(["/usr/lib", "/usr/local/bin", "usr//lib/", "/etc"]);
+
+        assert!(trie.contains(path_segments("usr/lib")));
+        assert!(!trie.contains(path_segments("/usr/local")));
+        assert!(trie.starts_with(path_segments("/usr/local")));
+        assert_eq!(
+            vec![vec!["usr", "lib"], vec!["usr", "local", "bin"]],
+            trie.auto_complete(path_segments("/usr"))
+        );
+    }
+
+    #[test]
+    fn render_views() {
+        let trie = Trie::from_strs(["to", "tea", "i"]);
+
+        let options = RenderOptions {
+            parent_links: false,
+            metadata: true,
+        };
+        let expected = concat!(
+            "i [end=true height=1]\n",
+            "t [end=false height=3]\n",
+            "├── e [end=false height=2]\n",
+            "│   └── a [end=true height=1]\n",
+            "└── o [end=true height=1]\n",
+        );
+        assert_eq!(expected, trie.to_ascii(&options));
+        assert!(trie.to_dot(&options).contains("n1 -> n2;"));
+    }
+}